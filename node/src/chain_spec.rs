@@ -1,16 +1,21 @@
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	AccountId, AllowlistConfig, AuraConfig, Balance, BalancesConfig, BlockNumber, GenesisConfig,
+	GrandpaConfig, NicknamesConfig, NodeAuthorizationConfig, Signature, SudoConfig, SystemConfig,
+	VestingConfig, DAYS, WASM_BINARY,
 };
+use sc_network::config::identity::{ed25519, PublicKey};
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{sr25519, Pair, Public};
+use sp_core::{sr25519, OpaquePeerId, Pair, Public};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{IdentifyAccount, Verify};
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// Initial balance of each endowed account.
+pub const ENDOWMENT: Balance = 1 << 60;
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
 
@@ -36,6 +41,47 @@ pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
 	(get_from_seed::<AuraId>(s), get_from_seed::<GrandpaId>(s))
 }
 
+/// Peer id of a node started with `--node-key` set to `n`, as a 32-byte hex number. The nodes of
+/// the dev and local chains are authorized under these peer ids, so with `--node-authorization`
+/// they must be started with these keys:
+///
+/// - Alice's node, peer id `12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp`, with `--node-key
+///   0000000000000000000000000000000000000000000000000000000000000001`.
+/// - Bob's node, peer id `12D3KooWHdiAxVd8uMQR1hGWXccidmfCwLqcMpGwR6QcTP6QRMuD`, with `--node-key
+///   0000000000000000000000000000000000000000000000000000000000000002`.
+pub fn peer_id_from_node_key(n: u8) -> OpaquePeerId {
+	let mut secret = [0u8; 32];
+	secret[31] = n;
+	let secret = ed25519::SecretKey::from_bytes(&mut secret).expect("32 bytes; qed");
+	let public = PublicKey::Ed25519(ed25519::Keypair::from(secret).public());
+	OpaquePeerId(public.to_peer_id().to_bytes())
+}
+
+/// Genesis names of the dev accounts of the given seeds, named after them.
+pub fn dev_names(seeds: &[&str]) -> Vec<(AccountId, Vec<u8>)> {
+	seeds
+		.iter()
+		.map(|seed| (get_account_id_from_seed::<sr25519::Public>(seed), seed.as_bytes().to_vec()))
+		.collect()
+}
+
+/// Genesis vesting schedule of an account endowed with `endowment`, in the format of the
+/// `vesting` section of the chain spec: all but `liquid` of the endowment is locked, and about
+/// `per_block` of it unlocks at every block from `start + cliff` on.
+pub fn vesting(
+	account: AccountId,
+	endowment: Balance,
+	start: BlockNumber,
+	cliff: BlockNumber,
+	per_block: Balance,
+	liquid: Balance,
+) -> (AccountId, BlockNumber, BlockNumber, Balance) {
+	let locked = endowment.saturating_sub(liquid);
+	let per_block = per_block.max(1);
+	let length = (locked + per_block - 1) / per_block;
+	(account, start + cliff, length.try_into().unwrap_or(BlockNumber::MAX), liquid)
+}
+
 pub fn development_config() -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
 
@@ -59,6 +105,23 @@ pub fn development_config() -> Result<ChainSpec, String> {
 					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 				],
+				// Authorized nodes, owned by Alice, started with `--node-key` set to 1
+				vec![(
+					peer_id_from_node_key(1),
+					get_account_id_from_seed::<sr25519::Public>("Alice"),
+				)],
+				// Account names
+				dev_names(&["Alice", "Bob"]),
+				// Vesting schedules: Bob's stash keeps a tenth of its endowment liquid, and the
+				// rest unlocks over about 100 blocks after a cliff of 10
+				vec![vesting(
+					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
+					ENDOWMENT,
+					0,
+					10,
+					ENDOWMENT / 10 * 9 / 100,
+					ENDOWMENT / 10,
+				)],
 				true,
 			)
 		},
@@ -107,6 +170,34 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 				],
+				// Authorized nodes of Alice and Bob, started with `--node-key` set to 1 and 2
+				vec![
+					(
+						peer_id_from_node_key(1),
+						get_account_id_from_seed::<sr25519::Public>("Alice"),
+					),
+					(peer_id_from_node_key(2), get_account_id_from_seed::<sr25519::Public>("Bob")),
+				],
+				// Account names
+				dev_names(&["Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie"]),
+				// Vesting schedules: the stash accounts keep a tenth of their endowment liquid,
+				// and the rest unlocks over 30 days after a cliff of a day
+				["Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie"]
+					.iter()
+					.map(|seed| {
+						vesting(
+							get_account_id_from_seed::<sr25519::Public>(&format!(
+								"{}//stash",
+								seed
+							)),
+							ENDOWMENT,
+							0,
+							DAYS,
+							ENDOWMENT / 10 * 9 / (30 * DAYS as Balance),
+							ENDOWMENT / 10,
+						)
+					})
+					.collect(),
 				true,
 			)
 		},
@@ -125,11 +216,14 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 }
 
 /// Configure initial storage state for FRAME modules.
-fn testnet_genesis(
+pub(crate) fn testnet_genesis(
 	wasm_binary: &[u8],
 	initial_authorities: Vec<(AuraId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	authorized_nodes: Vec<(OpaquePeerId, AccountId)>,
+	names: Vec<(AccountId, Vec<u8>)>,
+	vesting: Vec<(AccountId, BlockNumber, BlockNumber, Balance)>,
 	_enable_println: bool,
 ) -> GenesisConfig {
 	GenesisConfig {
@@ -138,8 +232,8 @@ fn testnet_genesis(
 			code: wasm_binary.to_vec(),
 		},
 		balances: BalancesConfig {
-			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
+			// Configure endowed accounts with initial balance of `ENDOWMENT`.
+			balances: endowed_accounts.iter().cloned().map(|k| (k, ENDOWMENT)).collect(),
 		},
 		aura: AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
@@ -149,8 +243,18 @@ fn testnet_genesis(
 		},
 		sudo: SudoConfig {
 			// Assign network admin rights.
-			key: Some(root_key),
+			key: Some(root_key.clone()),
 		},
 		transaction_payment: Default::default(),
+		allowlist: AllowlistConfig {
+			// Allow the endowed accounts and the network admin to sign transactions.
+			accounts: endowed_accounts.into_iter().chain(Some(root_key)).collect(),
+		},
+		node_authorization: NodeAuthorizationConfig {
+			// Only these nodes, and the connections their owners add, may join the network.
+			nodes: authorized_nodes,
+		},
+		nicknames: NicknamesConfig { names },
+		vesting: VestingConfig { vesting },
 	}
 }
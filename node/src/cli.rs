@@ -7,6 +7,12 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	#[clap(flatten)]
+	pub health: crate::health::HealthParams,
+
+	#[clap(flatten)]
+	pub node_authorization: crate::node_authorization::NodeAuthorizationParams,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -37,8 +43,7 @@ pub enum Subcommand {
 	Revert(sc_cli::RevertCmd),
 
 	/// Sub-commands concerned with benchmarking.
-	#[clap(subcommand)]
-	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+	Benchmark(BenchmarkCli),
 
 	/// Try some command against runtime state.
 	#[cfg(feature = "try-runtime")]
@@ -48,6 +53,44 @@ pub enum Subcommand {
 	#[cfg(not(feature = "try-runtime"))]
 	TryRuntime,
 
+	/// Check the invariants of the runtime's pallets against a live or saved chain state.
+	#[cfg(feature = "try-runtime")]
+	TryState(crate::try_state::TryStateCmd),
+
+	/// Check the invariants of the runtime's pallets. Note: `try-runtime` feature must be
+	/// enabled.
+	#[cfg(not(feature = "try-runtime"))]
+	TryState,
+
+	/// Check a candidate runtime for breaking changes before upgrading to it.
+	CheckUpgrade(crate::upgrade_check::CheckUpgradeCmd),
+
+	/// Report metadata changes between two runtimes that affect transactions or storage.
+	MetadataDiff(crate::metadata_diff::MetadataDiffCmd),
+
+	/// Build, sign and print or submit a transaction.
+	Tx(crate::tx::TxCmd),
+
+	/// Flood a node with pre-signed transactions and report how they are included.
+	LoadTest(crate::load_test::LoadTestCmd),
+
+	/// Export the extrinsics of a block range with decoded calls, results, fees and events.
+	ExportDecoded(crate::export_decoded::ExportDecodedCmd),
+
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
 }
+
+/// The benchmarking sub-commands, with the options this node adds to them.
+#[derive(Debug, clap::Parser)]
+pub struct BenchmarkCli {
+	#[allow(missing_docs)]
+	#[clap(subcommand)]
+	pub cmd: frame_benchmarking_cli::BenchmarkCmd,
+
+	/// Extrinsic that fills the blocks measured by `benchmark overhead`, as `pallet::extrinsic`.
+	/// Any extrinsic supported by `benchmark extrinsic` can be used, e.g.
+	/// `template::do_something`.
+	#[clap(long, global = true, default_value = "system::remark")]
+	pub overhead_extrinsic: String,
+}
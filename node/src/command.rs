@@ -1,7 +1,10 @@
 use crate::{
-	benchmarking::{inherent_benchmark_data, RemarkBuilder, TransferKeepAliveBuilder},
+	benchmarking::{
+		inherent_benchmark_data, RemarkBuilder, TemplateCauseErrorBuilder,
+		TemplateDoSomethingBuilder, TransferKeepAliveBuilder,
+	},
 	chain_spec,
-	cli::{Cli, Subcommand},
+	cli::{BenchmarkCli, Cli, Subcommand},
 	service,
 };
 use frame_benchmarking_cli::{BenchmarkCmd, ExtrinsicFactory, SUBSTRATE_REFERENCE_HARDWARE};
@@ -9,6 +12,7 @@ use node_template_runtime::{Block, EXISTENTIAL_DEPOSIT};
 use sc_cli::{ChainSpec, RuntimeVersion, SubstrateCli};
 use sc_service::PartialComponents;
 use sp_keyring::Sr25519Keyring;
+use std::sync::Arc;
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> String {
@@ -105,7 +109,7 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, backend, Some(aux_revert)), task_manager))
 			})
 		},
-		Some(Subcommand::Benchmark(cmd)) => {
+		Some(Subcommand::Benchmark(BenchmarkCli { cmd, overhead_extrinsic })) => {
 			let runner = cli.create_runner(cmd)?;
 
 			runner.sync_run(|config| {
@@ -137,21 +141,19 @@ pub fn run() -> sc_cli::Result<()> {
 					},
 					BenchmarkCmd::Overhead(cmd) => {
 						let PartialComponents { client, .. } = service::new_partial(&config)?;
-						let ext_builder = RemarkBuilder::new(client.clone());
+						let ext_factory = extrinsic_factory(client.clone());
+						let ext_builder = overhead_extrinsic
+							.split_once("::")
+							.and_then(|(pallet, extrinsic)| ext_factory.try_get(pallet, extrinsic))
+							.ok_or_else(|| {
+								format!("Unsupported overhead extrinsic: {}", overhead_extrinsic)
+							})?;
 
-						cmd.run(config, client, inherent_benchmark_data()?, &ext_builder)
+						cmd.run(config, client, inherent_benchmark_data()?, ext_builder)
 					},
 					BenchmarkCmd::Extrinsic(cmd) => {
 						let PartialComponents { client, .. } = service::new_partial(&config)?;
-						// Register the *Remark* and *TKA* builders.
-						let ext_factory = ExtrinsicFactory(vec![
-							Box::new(RemarkBuilder::new(client.clone())),
-							Box::new(TransferKeepAliveBuilder::new(
-								client.clone(),
-								Sr25519Keyring::Alice.to_account_id(),
-								EXISTENTIAL_DEPOSIT,
-							)),
-						]);
+						let ext_factory = extrinsic_factory(client.clone());
 
 						cmd.run(client, inherent_benchmark_data()?, &ext_factory)
 					},
@@ -177,6 +179,39 @@ pub fn run() -> sc_cli::Result<()> {
 		Some(Subcommand::TryRuntime) => Err("TryRuntime wasn't enabled when building the node. \
 				You can enable it with `--features try-runtime`."
 			.into()),
+		#[cfg(feature = "try-runtime")]
+		Some(Subcommand::TryState(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				// As for `try-runtime`, only a task manager is needed for `async_run`.
+				let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
+				let task_manager =
+					sc_service::TaskManager::new(config.tokio_handle.clone(), registry)
+						.map_err(|e| sc_cli::Error::Service(sc_service::Error::Prometheus(e)))?;
+				Ok((cmd.run(), task_manager))
+			})
+		},
+		#[cfg(not(feature = "try-runtime"))]
+		Some(Subcommand::TryState) => Err("TryState wasn't enabled when building the node. \
+				You can enable it with `--features try-runtime`."
+			.into()),
+		Some(Subcommand::CheckUpgrade(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(client, config.wasm_method, config.default_heap_pages)
+			})
+		},
+		Some(Subcommand::MetadataDiff(cmd)) => cmd.run(),
+		Some(Subcommand::Tx(cmd)) => cmd.run(),
+		Some(Subcommand::LoadTest(cmd)) => cmd.run(),
+		Some(Subcommand::ExportDecoded(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(client)
+			})
+		},
 		Some(Subcommand::ChainInfo(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
@@ -184,8 +219,26 @@ pub fn run() -> sc_cli::Result<()> {
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
 			runner.run_node_until_exit(|config| async move {
-				service::new_full(config).map_err(sc_cli::Error::Service)
+				let node_authorization = cli.node_authorization.node_authorization;
+				service::new_full(config, cli.health.config(), node_authorization)
+					.map(|node| node.task_manager)
+					.map_err(sc_cli::Error::Service)
 			})
 		},
 	}
 }
+
+/// The extrinsics supported by `benchmark extrinsic` and `benchmark overhead`.
+fn extrinsic_factory(client: Arc<service::FullClient>) -> ExtrinsicFactory {
+	// Register the *Remark*, *TKA* and *TemplateModule* builders.
+	ExtrinsicFactory(vec![
+		Box::new(RemarkBuilder::new(client.clone())),
+		Box::new(TransferKeepAliveBuilder::new(
+			client.clone(),
+			Sr25519Keyring::Alice.to_account_id(),
+			EXISTENTIAL_DEPOSIT,
+		)),
+		Box::new(TemplateDoSomethingBuilder::new(client.clone())),
+		Box::new(TemplateCauseErrorBuilder::new(client)),
+	])
+}
@@ -0,0 +1,538 @@
+//! Decoding extrinsics, calls, events and errors into JSON with the metadata of the runtime that
+//! produced them.
+
+use codec::{Compact, Decode};
+use frame_metadata::{
+	PalletMetadata, RuntimeMetadata, RuntimeMetadataPrefixed, RuntimeMetadataV14, StorageEntryType,
+};
+use node_template_runtime::Balance;
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefPrimitive, Variant};
+use serde_json::{json, Map, Value};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+use sp_runtime::DispatchError;
+
+/// Decode the metadata returned by the `Metadata_metadata` runtime API.
+pub fn metadata(bytes: &[u8]) -> Result<RuntimeMetadataV14, String> {
+	let prefixed = RuntimeMetadataPrefixed::decode(&mut &bytes[..])
+		.map_err(|e| format!("Failed to decode metadata: {}", e))?;
+	match prefixed.1 {
+		RuntimeMetadata::V14(metadata) => Ok(metadata),
+		_ => Err("Only V14 metadata is supported".into()),
+	}
+}
+
+/// A decoded extrinsic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extrinsic {
+	/// Address of the signer, for signed extrinsics.
+	pub signer: Option<Value>,
+	/// The signed extensions that encode any data, by identifier, or null for unsigned
+	/// extrinsics.
+	pub extensions: Value,
+	/// Name of the pallet of the call.
+	pub pallet: String,
+	/// Name of the call.
+	pub call: String,
+	/// Arguments of the call.
+	pub args: Value,
+}
+
+/// Decode an encoded extrinsic, starting with its length, into its signer, signed extensions
+/// and call.
+pub fn extrinsic(metadata: &RuntimeMetadataV14, bytes: &[u8]) -> Result<Extrinsic, String> {
+	let error = |e: codec::Error| format!("Failed to decode extrinsic: {}", e);
+	let mut input = bytes;
+	Compact::<u32>::decode(&mut input).map_err(error)?;
+	let version = u8::decode(&mut input).map_err(error)?;
+	if version & 0b0111_1111 != metadata.extrinsic.version {
+		return Err(format!("Unsupported extrinsic version {}", version & 0b0111_1111))
+	}
+
+	let (signer, extensions) = if version & 0b1000_0000 != 0 {
+		let types = &metadata.types;
+		let signer = decode_value(types, extrinsic_type_param(metadata, "Address")?, &mut input)?;
+		decode_value(types, extrinsic_type_param(metadata, "Signature")?, &mut input)?;
+		let mut extensions = Map::new();
+		for extension in &metadata.extrinsic.signed_extensions {
+			let len = input.len();
+			let value = decode_value(types, extension.ty.id(), &mut input)?;
+			if input.len() < len {
+				extensions.insert(extension.identifier.clone(), value);
+			}
+		}
+		(Some(signer), extensions.into())
+	} else {
+		(None, Value::Null)
+	};
+	let (pallet, name, args) = call(metadata, input)?;
+
+	Ok(Extrinsic { signer, extensions, pallet, call: name, args })
+}
+
+/// Decode an encoded runtime call into its pallet name, call name and arguments.
+pub fn call(metadata: &RuntimeMetadataV14, call: &[u8]) -> Result<(String, String, Value), String> {
+	decode_enum(metadata, call, |pallet| pallet.calls.as_ref().map(|calls| calls.ty.id()))
+}
+
+/// Decode an encoded runtime event into `{"pallet", "event", "fields"}`.
+pub fn event(metadata: &RuntimeMetadataV14, event: &[u8]) -> Result<Value, String> {
+	let (pallet, name, fields) =
+		decode_enum(metadata, event, |pallet| pallet.event.as_ref().map(|event| event.ty.id()))?;
+	Ok(json!({ "pallet": pallet, "event": name, "fields": fields }))
+}
+
+/// An event deposited by a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventRecord {
+	/// Index of the extrinsic that deposited the event, `None` for events deposited while
+	/// initializing or finalizing the block.
+	pub extrinsic: Option<u32>,
+	/// The event as `{"pallet", "event", "fields"}`, like [`event`] returns it.
+	pub event: Value,
+}
+
+/// Decode the value of the `System::Events` storage of a block.
+pub fn events(metadata: &RuntimeMetadataV14, bytes: &[u8]) -> Result<Vec<EventRecord>, String> {
+	let types = &metadata.types;
+	let id = metadata
+		.pallets
+		.iter()
+		.find(|pallet| pallet.name == "System")
+		.and_then(|pallet| pallet.storage.as_ref()?.entries.iter().find(|e| e.name == "Events"))
+		.and_then(|entry| match &entry.ty {
+			StorageEntryType::Plain(ty) => Some(ty.id()),
+			StorageEntryType::Map { .. } => None,
+		})
+		.ok_or("The metadata has no System::Events storage")?;
+	let record = match types.resolve(id).map(|ty| ty.type_def()) {
+		Some(TypeDef::Sequence(sequence)) => sequence.type_param().id(),
+		_ => return Err("System::Events is not a sequence".into()),
+	};
+	let fields = match types.resolve(record).map(|ty| ty.type_def()) {
+		Some(TypeDef::Composite(composite)) => composite.fields(),
+		_ => return Err("System::Events does not hold structs".into()),
+	};
+
+	let mut input = bytes;
+	let len = Compact::<u32>::decode(&mut input)
+		.map_err(|e| format!("Failed to decode events: {}", e))?
+		.0;
+	(0..len)
+		.map(|_| -> Result<_, String> {
+			let mut record = EventRecord { extrinsic: None, event: Value::Null };
+			for field in fields {
+				let id = field.ty().id();
+				match field.name().map(String::as_str) {
+					Some("phase") => {
+						let phase = decode_value(types, id, &mut input)?;
+						record.extrinsic = phase
+							.get("ApplyExtrinsic")
+							.and_then(Value::as_u64)
+							.map(|index| index as u32);
+					},
+					Some("event") => {
+						let (pallet, name, fields) = decode_outer_enum(types, id, &mut input)?;
+						record.event = json!({ "pallet": pallet, "event": name, "fields": fields });
+					},
+					_ => {
+						decode_value(types, id, &mut input)?;
+					},
+				}
+			}
+			Ok(record)
+		})
+		.collect()
+}
+
+/// Balances as JSON numbers when they fit, as strings otherwise, so no precision is lost.
+pub fn balance(value: Balance) -> Value {
+	match u64::try_from(value) {
+		Ok(value) => value.into(),
+		Err(_) => value.to_string().into(),
+	}
+}
+
+/// The balance in `value`, as [`balance`] writes it.
+pub fn parse_balance(value: &Value) -> Option<Balance> {
+	value.as_u64().map(Into::into).or_else(|| value.as_str()?.parse().ok())
+}
+
+/// Name `error`, using the error names of the pallets for module errors.
+pub fn dispatch_error(metadata: &RuntimeMetadataV14, error: &DispatchError) -> String {
+	if let DispatchError::Module(module) = error {
+		if let Some(name) = module_error(metadata, module.index, module.error[0]) {
+			return name
+		}
+	}
+	format!("{:?}", error)
+}
+
+/// Name a dispatch error decoded into JSON, e.g. from the fields of an event, like
+/// [`dispatch_error`] does.
+pub fn dispatch_error_value(metadata: &RuntimeMetadataV14, error: &Value) -> String {
+	let module = error.get("Module");
+	let index = module.and_then(|m| m.get("index")).and_then(Value::as_u64);
+	// The error index is the first byte of the hex encoded `error` array.
+	let error_index = module
+		.and_then(|m| m.get("error"))
+		.and_then(Value::as_str)
+		.and_then(|hex| u8::from_str_radix(hex.get(2..4)?, 16).ok());
+	if let (Some(index), Some(error_index)) = (index, error_index) {
+		if let Some(name) = module_error(metadata, index as u8, error_index) {
+			return name
+		}
+	}
+	match error {
+		Value::String(name) => name.clone(),
+		other => other.to_string(),
+	}
+}
+
+/// Name the error `error` of the pallet with index `pallet`.
+fn module_error(metadata: &RuntimeMetadataV14, pallet: u8, error: u8) -> Option<String> {
+	let pallet = metadata.pallets.iter().find(|p| p.index == pallet)?;
+	let ty = metadata.types.resolve(pallet.error.as_ref()?.ty.id())?;
+	match ty.type_def() {
+		TypeDef::Variant(variant) => variant
+			.variants()
+			.iter()
+			.find(|v| v.index() == error)
+			.map(|v| format!("{}::{}", pallet.name, v.name())),
+		_ => None,
+	}
+}
+
+/// The type of the type parameter `name` of the extrinsic type, e.g. of its `Address`.
+fn extrinsic_type_param(metadata: &RuntimeMetadataV14, name: &str) -> Result<u32, String> {
+	metadata
+		.types
+		.resolve(metadata.extrinsic.ty.id())
+		.and_then(|ty| ty.type_params().iter().find(|param| param.name() == name))
+		.and_then(|param| param.ty())
+		.map(|ty| ty.id())
+		.ok_or_else(|| format!("The extrinsic type has no {} parameter", name))
+}
+
+/// Decode a call or event of the outer runtime enum, encoded as the pallet index, the variant
+/// index and the fields, into the pallet name, the variant name and the fields.
+///
+/// `enum_type` gives the type of the pallet's own enum, e.g. of its calls.
+fn decode_enum(
+	metadata: &RuntimeMetadataV14,
+	bytes: &[u8],
+	enum_type: impl Fn(&PalletMetadata<PortableForm>) -> Option<u32>,
+) -> Result<(String, String, Value), String> {
+	let (&pallet_index, mut input) = bytes.split_first().ok_or("Empty call or event")?;
+	let pallet = metadata
+		.pallets
+		.iter()
+		.find(|p| p.index == pallet_index)
+		.ok_or_else(|| format!("No pallet with index {}", pallet_index))?;
+	let id = enum_type(pallet).ok_or_else(|| format!("Pallet {} has no such enum", pallet.name))?;
+	let variant = match metadata.types.resolve(id).map(|ty| ty.type_def()) {
+		Some(TypeDef::Variant(variant)) => variant,
+		_ => return Err(format!("Type {} of pallet {} is not an enum", id, pallet.name)),
+	};
+	let index = u8::decode(&mut input).map_err(|e| e.to_string())?;
+	let variant = variant
+		.variants()
+		.iter()
+		.find(|v| v.index() == index)
+		.ok_or_else(|| format!("Pallet {} has no variant {}", pallet.name, index))?;
+	let fields = decode_fields(&metadata.types, variant.fields(), &mut input)?;
+
+	Ok((pallet.name.clone(), variant.name().clone(), fields))
+}
+
+/// Decode a value of an outer runtime enum of type `id`, whose variants wrap the enum of each
+/// pallet, into the pallet name, the variant name and the fields.
+fn decode_outer_enum(
+	types: &PortableRegistry,
+	id: u32,
+	input: &mut &[u8],
+) -> Result<(String, String, Value), String> {
+	let pallet = read_variant(types, id, input)?;
+	let inner = match pallet.fields() {
+		[field] => field.ty().id(),
+		_ => return Err(format!("Variant {} does not wrap the enum of a pallet", pallet.name())),
+	};
+	let variant = read_variant(types, inner, input)?;
+	let fields = decode_fields(types, variant.fields(), input)?;
+
+	Ok((pallet.name().clone(), variant.name().clone(), fields))
+}
+
+/// Read the index of a variant of the enum type `id` from `input` and find the variant.
+fn read_variant<'a>(
+	types: &'a PortableRegistry,
+	id: u32,
+	input: &mut &[u8],
+) -> Result<&'a Variant<PortableForm>, String> {
+	let ty = types.resolve(id).ok_or_else(|| format!("Unknown type {}", id))?;
+	let variants = match ty.type_def() {
+		TypeDef::Variant(variant) => variant.variants(),
+		_ => return Err(format!("{:?} is not an enum", ty.path())),
+	};
+	let index =
+		u8::decode(input).map_err(|e| format!("Failed to decode {:?}: {}", ty.path(), e))?;
+	variants
+		.iter()
+		.find(|v| v.index() == index)
+		.ok_or_else(|| format!("No variant {} in {:?}", index, ty.path()))
+}
+
+/// Decode a value of type `id` from `input` into JSON.
+///
+/// Structs become objects, enums the variant name or an object with the variant name as key,
+/// byte arrays hex strings and accounts SS58 addresses.
+fn decode_value(types: &PortableRegistry, id: u32, input: &mut &[u8]) -> Result<Value, String> {
+	let ty = types.resolve(id).ok_or_else(|| format!("Unknown type {}", id))?;
+	let error = |e: codec::Error| format!("Failed to decode {:?}: {}", ty.path(), e);
+
+	Ok(match ty.type_def() {
+		TypeDef::Composite(_) if ty.path().ident().as_deref() == Some("AccountId32") =>
+			AccountId32::decode(input).map_err(error)?.to_ss58check().into(),
+		TypeDef::Composite(composite) => decode_fields(types, composite.fields(), input)?,
+		TypeDef::Variant(_) => {
+			let variant = read_variant(types, id, input)?;
+			match decode_fields(types, variant.fields(), input)? {
+				Value::Null => variant.name().clone().into(),
+				fields => json!({ variant.name(): fields }),
+			}
+		},
+		TypeDef::Sequence(sequence) => {
+			let len = Compact::<u32>::decode(input).map_err(error)?.0;
+			decode_items(types, sequence.type_param().id(), len, input)?
+		},
+		TypeDef::Array(array) => decode_items(types, array.type_param().id(), array.len(), input)?,
+		TypeDef::Tuple(tuple) => tuple
+			.fields()
+			.iter()
+			.map(|f| decode_value(types, f.id(), input))
+			.collect::<Result<_, _>>()?,
+		TypeDef::Primitive(primitive) => decode_primitive(primitive, input).map_err(error)?,
+		TypeDef::Compact(compact) => {
+			let value = balance(Compact::<u128>::decode(input).map_err(error)?.0);
+			match types.resolve(compact.type_param().id()).map(|ty| ty.type_def()) {
+				// Compact wrappers like `Perbill` encode their single field as a compact integer,
+				// so only the field remains to be wrapped like `decode_fields` would.
+				Some(TypeDef::Composite(composite)) =>
+					match composite.fields().first().and_then(|field| field.name()) {
+						Some(name) => json!({ name: value }),
+						None => value,
+					},
+				_ => value,
+			}
+		},
+		TypeDef::BitSequence(_) => return Err("Bit sequences are not supported".into()),
+	})
+}
+
+/// Decode `fields` into an object if they are named, the value itself for a single unnamed
+/// field, an array for several, or null for none.
+fn decode_fields(
+	types: &PortableRegistry,
+	fields: &[Field<PortableForm>],
+	input: &mut &[u8],
+) -> Result<Value, String> {
+	match fields {
+		[] => Ok(Value::Null),
+		[field] if field.name().is_none() => decode_value(types, field.ty().id(), input),
+		_ if fields.iter().all(|f| f.name().is_some()) => {
+			let mut object = Map::new();
+			for field in fields {
+				let name = field.name().expect("all fields are named; qed").clone();
+				object.insert(name, decode_value(types, field.ty().id(), input)?);
+			}
+			Ok(object.into())
+		},
+		_ => fields.iter().map(|f| decode_value(types, f.ty().id(), input)).collect(),
+	}
+}
+
+/// Decode `len` items of type `id`, as a hex string if they are bytes.
+fn decode_items(
+	types: &PortableRegistry,
+	id: u32,
+	len: u32,
+	input: &mut &[u8],
+) -> Result<Value, String> {
+	let is_byte = matches!(
+		types.resolve(id).map(|ty| ty.type_def()),
+		Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+	);
+	if is_byte {
+		let len = len as usize;
+		if input.len() < len {
+			return Err("Not enough data for byte array".into())
+		}
+		let (bytes, rest) = input.split_at(len);
+		*input = rest;
+		return Ok(format!("0x{}", hex(bytes)).into())
+	}
+	(0..len).map(|_| decode_value(types, id, input)).collect()
+}
+
+fn decode_primitive(
+	primitive: &TypeDefPrimitive,
+	input: &mut &[u8],
+) -> Result<Value, codec::Error> {
+	Ok(match primitive {
+		TypeDefPrimitive::Bool => bool::decode(input)?.into(),
+		TypeDefPrimitive::Char => char::from_u32(u32::decode(input)?)
+			.ok_or_else(|| codec::Error::from("Invalid char"))?
+			.to_string()
+			.into(),
+		TypeDefPrimitive::Str => String::decode(input)?.into(),
+		TypeDefPrimitive::U8 => u8::decode(input)?.into(),
+		TypeDefPrimitive::U16 => u16::decode(input)?.into(),
+		TypeDefPrimitive::U32 => u32::decode(input)?.into(),
+		TypeDefPrimitive::U64 => u64::decode(input)?.into(),
+		TypeDefPrimitive::U128 => balance(u128::decode(input)?),
+		TypeDefPrimitive::U256 => format!("0x{}", hex(&<[u8; 32]>::decode(input)?)).into(),
+		TypeDefPrimitive::I8 => i8::decode(input)?.into(),
+		TypeDefPrimitive::I16 => i16::decode(input)?.into(),
+		TypeDefPrimitive::I32 => i32::decode(input)?.into(),
+		TypeDefPrimitive::I64 => i64::decode(input)?.into(),
+		TypeDefPrimitive::I128 => i128::decode(input)?.to_string().into(),
+		TypeDefPrimitive::I256 => format!("0x{}", hex(&<[u8; 32]>::decode(input)?)).into(),
+	})
+}
+
+fn hex(bytes: &[u8]) -> String {
+	sp_core::hexdisplay::HexDisplay::from(&bytes).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::extrinsic::{ChainContext, SignedExtrinsicBuilder};
+	use codec::Encode;
+	use frame_system::Phase;
+	use node_template_runtime::{
+		pallet_template, Call, Event, Hash, Runtime, TemplateCall, UncheckedExtrinsic, VERSION,
+	};
+	use scale_info::{meta_type, Registry};
+	use sp_keyring::Sr25519Keyring;
+	use sp_runtime::Perbill;
+
+	fn runtime_metadata() -> RuntimeMetadataV14 {
+		metadata(&Runtime::metadata()).unwrap()
+	}
+
+	#[test]
+	fn compact_wrappers_are_decoded_as_their_field() {
+		let mut registry = Registry::new();
+		let id = registry.register_type(&meta_type::<(Compact<Perbill>, u8)>()).id();
+		let types = PortableRegistry::from(registry);
+		// The byte after the compact value shows that it was read with its compact length.
+		let encoded = (Compact(Perbill::from_percent(50)), 7u8).encode();
+		assert_eq!(decode_value(&types, id, &mut &encoded[..]), Ok(json!([500_000_000, 7])));
+	}
+
+	#[test]
+	fn calls_are_decoded_with_names_and_arguments() {
+		let metadata = runtime_metadata();
+		let encoded = Call::from(TemplateCall::do_something { something: 42 }).encode();
+		assert_eq!(
+			call(&metadata, &encoded),
+			Ok(("TemplateModule".into(), "do_something".into(), json!({ "something": 42 })))
+		);
+	}
+
+	#[test]
+	fn events_are_decoded_with_accounts_as_addresses() {
+		let metadata = runtime_metadata();
+		let bob = Sr25519Keyring::Bob.to_account_id();
+		let encoded =
+			Event::from(pallet_template::Event::<Runtime>::SomethingStored(7, bob.clone()))
+				.encode();
+		assert_eq!(
+			event(&metadata, &encoded),
+			Ok(json!({
+				"pallet": "TemplateModule",
+				"event": "SomethingStored",
+				"fields": [7, bob.to_ss58check()],
+			}))
+		);
+	}
+
+	#[test]
+	fn extrinsics_are_decoded_with_their_signed_extensions() {
+		let metadata = runtime_metadata();
+		let call = TemplateCall::<Runtime>::do_something { something: 42 };
+		let context = ChainContext::new(Hash::zero(), &VERSION);
+		let xt =
+			SignedExtrinsicBuilder::new(Sr25519Keyring::Alice.pair(), context, Hash::zero(), 0)
+				.nonce(3)
+				.tip(5)
+				.sign(call.clone());
+
+		let decoded = extrinsic(&metadata, &xt.encode()).unwrap();
+		let alice = Sr25519Keyring::Alice.to_account_id().to_ss58check();
+		assert_eq!(decoded.signer, Some(json!({ "Id": alice })));
+		assert_eq!(decoded.extensions["CheckNonce"], 3);
+		assert_eq!(decoded.extensions["ChargeTransactionPayment"], 5);
+		// Extensions that only sign data do not show up.
+		assert_eq!(decoded.extensions.get("CheckGenesis"), None);
+		assert_eq!(
+			(decoded.pallet.as_str(), decoded.call.as_str()),
+			("TemplateModule", "do_something")
+		);
+		assert_eq!(decoded.args, json!({ "something": 42 }));
+
+		let unsigned = UncheckedExtrinsic::new_unsigned(call.into());
+		let decoded = extrinsic(&metadata, &unsigned.encode()).unwrap();
+		assert_eq!((decoded.signer, decoded.extensions), (None, Value::Null));
+	}
+
+	#[test]
+	fn event_records_are_decoded_with_their_extrinsic() {
+		let metadata = runtime_metadata();
+		let bob = Sr25519Keyring::Bob.to_account_id();
+		let failed = frame_system::Event::<Runtime>::ExtrinsicFailed {
+			dispatch_error: pallet_template::Error::<Runtime>::NoneValue.into(),
+			dispatch_info: Default::default(),
+		};
+		let records = vec![
+			frame_system::EventRecord {
+				phase: Phase::Initialization,
+				event: Event::from(pallet_template::Event::<Runtime>::SomethingStored(
+					7,
+					bob.clone(),
+				)),
+				topics: Vec::<Hash>::new(),
+			},
+			frame_system::EventRecord {
+				phase: Phase::ApplyExtrinsic(2),
+				event: Event::from(failed),
+				topics: vec![Hash::repeat_byte(1)],
+			},
+		];
+
+		let decoded = events(&metadata, &records.encode()).unwrap();
+		assert_eq!(decoded.iter().map(|r| r.extrinsic).collect::<Vec<_>>(), vec![None, Some(2)]);
+		assert_eq!(
+			decoded[0].event,
+			json!({
+				"pallet": "TemplateModule",
+				"event": "SomethingStored",
+				"fields": [7, bob.to_ss58check()],
+			})
+		);
+		assert_eq!(decoded[1].event["event"], "ExtrinsicFailed");
+		assert_eq!(
+			dispatch_error_value(&metadata, &decoded[1].event["fields"]["dispatch_error"]),
+			"TemplateModule::NoneValue"
+		);
+		assert_eq!(dispatch_error_value(&metadata, &json!("BadOrigin")), "BadOrigin");
+	}
+
+	#[test]
+	fn module_errors_are_named() {
+		let metadata = runtime_metadata();
+		let error: DispatchError = pallet_template::Error::<Runtime>::NoneValue.into();
+		assert_eq!(dispatch_error(&metadata, &error), "TemplateModule::NoneValue");
+		assert_eq!(dispatch_error(&metadata, &DispatchError::BadOrigin), "BadOrigin");
+	}
+}
@@ -0,0 +1,36 @@
+//! Reading the events deposited by blocks from the client's state.
+
+use codec::Decode;
+use frame_support::storage::StorageValue;
+use frame_system::EventRecord;
+use node_template_runtime::{opaque::Block, Event, Hash, Runtime};
+use sc_client_api::{Backend, StorageProvider};
+use sp_core::storage::StorageKey;
+use sp_runtime::generic::BlockId;
+
+/// The events of block `hash`, decoded with the native runtime's types.
+pub fn block_events<C, B>(client: &C, hash: Hash) -> Result<Vec<EventRecord<Event, Hash>>, String>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	match encoded_block_events(client, hash)? {
+		Some(data) => Vec::decode(&mut &data[..])
+			.map_err(|e| format!("Failed to decode the events of {:?}: {}", hash, e)),
+		None => Ok(Vec::new()),
+	}
+}
+
+/// The encoded events of block `hash`, for decoding with the metadata of the runtime that
+/// deposited them. `None` if the block deposited no events.
+pub fn encoded_block_events<C, B>(client: &C, hash: Hash) -> Result<Option<Vec<u8>>, String>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let key = StorageKey(frame_system::Events::<Runtime>::hashed_key().to_vec());
+	client
+		.storage(&BlockId::Hash(hash), &key)
+		.map(|data| data.map(|data| data.0))
+		.map_err(|e| format!("Failed to read the events of {:?}: {}", hash, e))
+}
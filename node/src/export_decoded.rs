@@ -0,0 +1,405 @@
+//! The `export-decoded` subcommand: export the extrinsics of a range of blocks from the local
+//! database with their decoded calls, dispatch results, fees and events, for analytics.
+//!
+//! Extrinsics, with their signed extensions, and events are decoded with the metadata of the
+//! runtime that executed each block rather than with this node's runtime types, so the output
+//! follows runtime upgrades.
+//!
+//! That metadata is read from the state of each block's parent and the events from the state
+//! of the block, so the exported blocks must not have been pruned. To export old blocks, use a
+//! database synced with `--state-pruning archive`.
+
+use crate::{
+	decode::{self, EventRecord},
+	events::encoded_block_events,
+	service::FullClient,
+};
+use codec::Encode;
+use frame_metadata::RuntimeMetadataV14;
+use node_template_runtime::{Balance, BlockNumber, Hash, Header};
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::{BlockBackend, HeaderBackend};
+use serde_json::{json, Value};
+use sp_api::{Metadata, ProvideRuntimeApi};
+use sp_runtime::{generic::BlockId, traits::Header as _};
+use std::{
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
+
+/// Columns of the CSV output. `args`, `extensions` and `events` hold JSON.
+const CSV_HEADER: &str = concat!(
+	"block_number,block_hash,index,pallet,call,signer,success,error,fee,tip,",
+	"args,extensions,events",
+);
+
+/// Export the extrinsics of a block range with their decoded calls, results, fees and events.
+///
+/// For example `export-decoded --from 1 --to 1000 --format csv --output blocks.csv`. Needs the
+/// state of the exported blocks and of their parents, see the module documentation.
+#[derive(Debug, clap::Parser)]
+pub struct ExportDecodedCmd {
+	/// First block to export.
+	#[clap(long, default_value_t = 1)]
+	pub from: BlockNumber,
+
+	/// Last block to export. Defaults to the best block.
+	#[clap(long)]
+	pub to: Option<BlockNumber>,
+
+	/// Output format, `jsonl` for one JSON object per extrinsic, or `csv`.
+	#[clap(long, default_value = "jsonl")]
+	pub format: Format,
+
+	/// File to write to. Defaults to standard output.
+	#[clap(long, value_name = "FILE")]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// Output format of [`ExportDecodedCmd`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// One JSON object per line.
+	JsonLines,
+	/// Comma-separated values with a header row.
+	Csv,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"jsonl" => Ok(Self::JsonLines),
+			"csv" => Ok(Self::Csv),
+			other => Err(format!("Unknown format `{}`, expected `jsonl` or `csv`", other)),
+		}
+	}
+}
+
+impl ExportDecodedCmd {
+	/// Export the blocks from the database of `client`.
+	pub fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+		let from = self.from.max(1);
+		let to = self.to.unwrap_or_else(|| client.info().best_number);
+		// State is pruned oldest block first, so if the parent of the first block still has its
+		// state, every later block has too.
+		if from <= to && client.state_at(&BlockId::Number(from - 1)).is_err() {
+			return Err(format!(
+				"The state of block {} is not available, it was likely pruned. Export from a \
+				 database synced with `--state-pruning archive`, or pass a later `--from`.",
+				from - 1
+			)
+			.into())
+		}
+		let mut out: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+			None => Box::new(BufWriter::new(io::stdout())),
+		};
+		if self.format == Format::Csv {
+			writeln!(out, "{}", CSV_HEADER)?;
+		}
+
+		// Metadata of the runtime that executed the last block, by spec version.
+		let mut metadata: Option<(u32, RuntimeMetadataV14)> = None;
+		for number in from..=to {
+			let hash = client
+				.hash(number)
+				.map_err(|e| format!("Failed to read block {}: {}", number, e))?
+				.ok_or_else(|| format!("Block {} not found", number))?;
+			let header: Header = client
+				.header(BlockId::Hash(hash))
+				.map_err(|e| format!("Failed to read block {}: {}", number, e))?
+				.ok_or_else(|| format!("Block {} not found", number))?;
+
+			// A block is executed by the runtime in the state of its parent.
+			let parent = BlockId::Hash(*header.parent_hash());
+			let version = client
+				.runtime_version_at(&parent)
+				.map_err(|e| format!("Failed to read the runtime version: {}", e))?;
+			if metadata.as_ref().map(|(spec_version, _)| *spec_version) !=
+				Some(version.spec_version)
+			{
+				let bytes = client
+					.runtime_api()
+					.metadata(&parent)
+					.map_err(|e| format!("Failed to read the metadata: {}", e))?;
+				metadata = Some((version.spec_version, decode::metadata(&bytes)?));
+			}
+			let metadata = &metadata.as_ref().expect("set above; qed").1;
+
+			let body = client
+				.block_body(&BlockId::Hash(hash))
+				.map_err(|e| format!("Failed to read block {}: {}", number, e))?
+				.ok_or_else(|| format!("Block {} has no body", number))?;
+			let events = match encoded_block_events(&*client, hash)? {
+				Some(events) => decode::events(metadata, &events)
+					.map_err(|e| format!("Failed to decode the events of {}: {}", number, e))?,
+				None => Vec::new(),
+			};
+			for (index, xt) in body.iter().enumerate() {
+				let xt = decode::extrinsic(metadata, &xt.encode()).map_err(|e| {
+					format!("Failed to decode extrinsic {}-{}: {}", number, index, e)
+				})?;
+				let events = events.iter().filter(|record| record.extrinsic == Some(index as u32));
+				let row = Row::new(metadata, number, hash, index as u32, xt, events);
+				match self.format {
+					Format::JsonLines => writeln!(out, "{}", row.to_json())?,
+					Format::Csv => writeln!(out, "{}", row.to_csv())?,
+				}
+			}
+		}
+
+		out.flush()?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportDecodedCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// An exported extrinsic.
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+	block_number: BlockNumber,
+	block_hash: Hash,
+	index: u32,
+	pallet: String,
+	call: String,
+	args: Value,
+	extensions: Value,
+	signer: Option<String>,
+	success: bool,
+	error: Option<String>,
+	fee: Option<Balance>,
+	tip: Option<Balance>,
+	events: Vec<Value>,
+}
+
+impl Row {
+	fn new<'a>(
+		metadata: &RuntimeMetadataV14,
+		block_number: BlockNumber,
+		block_hash: Hash,
+		index: u32,
+		xt: decode::Extrinsic,
+		events: impl Iterator<Item = &'a EventRecord>,
+	) -> Self {
+		// Accounts are written as their address, other kinds of addresses as JSON.
+		let signer = xt.signer.map(|address| match address.get("Id") {
+			Some(Value::String(account)) => account.clone(),
+			_ => address.to_string(),
+		});
+
+		let mut row = Self {
+			block_number,
+			block_hash,
+			index,
+			pallet: xt.pallet,
+			call: xt.call,
+			args: xt.args,
+			extensions: xt.extensions,
+			signer,
+			success: true,
+			error: None,
+			fee: None,
+			tip: None,
+			events: Vec::new(),
+		};
+		for record in events {
+			let event = &record.event;
+			let field = |name: &str| event["fields"].get(name);
+			match (event["pallet"].as_str(), event["event"].as_str()) {
+				(Some("System"), Some("ExtrinsicFailed")) => {
+					row.success = false;
+					row.error =
+						field("dispatch_error").map(|e| decode::dispatch_error_value(metadata, e));
+				},
+				(Some("TransactionPayment"), Some("TransactionFeePaid")) => {
+					row.fee = field("actual_fee").and_then(decode::parse_balance);
+					row.tip = field("tip").and_then(decode::parse_balance);
+				},
+				_ => {},
+			}
+			row.events.push(event.clone());
+		}
+		row
+	}
+
+	fn to_json(&self) -> Value {
+		json!({
+			"block_number": self.block_number,
+			"block_hash": format!("{:?}", self.block_hash),
+			"index": self.index,
+			"pallet": self.pallet,
+			"call": self.call,
+			"args": self.args,
+			"extensions": self.extensions,
+			"signer": self.signer,
+			"success": self.success,
+			"error": self.error,
+			"fee": self.fee.map(decode::balance),
+			"tip": self.tip.map(decode::balance),
+			"events": self.events,
+		})
+	}
+
+	fn to_csv(&self) -> String {
+		let optional = |value: Option<Balance>| value.map(|v| v.to_string()).unwrap_or_default();
+		[
+			self.block_number.to_string(),
+			format!("{:?}", self.block_hash),
+			self.index.to_string(),
+			self.pallet.clone(),
+			self.call.clone(),
+			self.signer.clone().unwrap_or_default(),
+			self.success.to_string(),
+			self.error.clone().unwrap_or_default(),
+			optional(self.fee),
+			optional(self.tip),
+			self.args.to_string(),
+			self.extensions.to_string(),
+			Value::from(self.events.clone()).to_string(),
+		]
+		.iter()
+		.map(|field| csv_field(field))
+		.collect::<Vec<_>>()
+		.join(",")
+	}
+}
+
+/// Quote `field` for CSV if it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+	if field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::extrinsic::{ChainContext, SignedExtrinsicBuilder};
+	use frame_system::Phase;
+	use node_template_runtime::{pallet_template, Event, Runtime, TemplateCall, VERSION};
+	use sp_core::crypto::Ss58Codec;
+	use sp_keyring::Sr25519Keyring;
+
+	/// Row of a `cause_error` extrinsic of Alice, extrinsic 1 of block 5, with its events.
+	fn failed_row() -> Row {
+		let metadata = decode::metadata(&Runtime::metadata()).unwrap();
+		let context = ChainContext::new(Hash::zero(), &VERSION);
+		let xt =
+			SignedExtrinsicBuilder::new(Sr25519Keyring::Alice.pair(), context, Hash::zero(), 0)
+				.nonce(3)
+				.tip(10)
+				.sign(TemplateCall::<Runtime>::cause_error {});
+		let fee_paid = pallet_transaction_payment::Event::<Runtime>::TransactionFeePaid {
+			who: Sr25519Keyring::Alice.to_account_id(),
+			actual_fee: 1_000,
+			tip: 10,
+		};
+		let failed = frame_system::Event::<Runtime>::ExtrinsicFailed {
+			dispatch_error: pallet_template::Error::<Runtime>::NoneValue.into(),
+			dispatch_info: Default::default(),
+		};
+		let records = [Event::from(fee_paid), Event::from(failed)]
+			.into_iter()
+			.map(|event| frame_system::EventRecord {
+				phase: Phase::ApplyExtrinsic(1),
+				event,
+				topics: Vec::<Hash>::new(),
+			})
+			.collect::<Vec<_>>();
+
+		let xt = decode::extrinsic(&metadata, &xt.encode()).unwrap();
+		let events = decode::events(&metadata, &records.encode()).unwrap();
+		Row::new(&metadata, 5, Hash::repeat_byte(1), 1, xt, events.iter())
+	}
+
+	#[test]
+	fn rows_take_the_result_fee_and_tip_from_the_events() {
+		let row = failed_row();
+		let alice = Sr25519Keyring::Alice.to_account_id().to_ss58check();
+		assert_eq!(row.signer, Some(alice));
+		assert_eq!((row.pallet.as_str(), row.call.as_str()), ("TemplateModule", "cause_error"));
+		assert!(!row.success);
+		assert_eq!(row.error.as_deref(), Some("TemplateModule::NoneValue"));
+		assert_eq!((row.fee, row.tip), (Some(1_000), Some(10)));
+		assert_eq!(row.extensions["CheckNonce"], 3);
+		assert_eq!(row.events.len(), 2);
+	}
+
+	#[test]
+	fn rows_are_written_as_json() {
+		let row = failed_row();
+		let json = row.to_json();
+		assert_eq!(json["block_number"], 5);
+		assert_eq!(json["block_hash"], format!("{:?}", Hash::repeat_byte(1)));
+		assert_eq!(json["index"], 1);
+		assert_eq!(json["signer"], json!(row.signer));
+		assert_eq!(json["success"], false);
+		assert_eq!(json["error"], "TemplateModule::NoneValue");
+		assert_eq!((&json["fee"], &json["tip"]), (&json!(1_000), &json!(10)));
+		assert_eq!(json["args"], Value::Null);
+		assert_eq!(json["extensions"], row.extensions);
+		assert_eq!(json["events"], json!(row.events));
+	}
+
+	#[test]
+	fn rows_are_written_as_csv_in_the_order_of_the_header() {
+		let row = failed_row();
+		let columns = [
+			"5".to_string(),
+			format!("{:?}", Hash::repeat_byte(1)),
+			"1".into(),
+			"TemplateModule".into(),
+			"cause_error".into(),
+			row.signer.clone().unwrap(),
+			"false".into(),
+			"TemplateModule::NoneValue".into(),
+			"1000".into(),
+			"10".into(),
+			"null".into(),
+			csv_field(&row.extensions.to_string()),
+			csv_field(&Value::from(row.events.clone()).to_string()),
+		];
+		assert_eq!(columns.len(), CSV_HEADER.split(',').count());
+		assert_eq!(row.to_csv(), columns.join(","));
+		// The JSON columns hold commas, so they must be quoted.
+		assert!(row.to_csv().ends_with("]\""));
+	}
+
+	#[test]
+	fn csv_fields_are_quoted_when_needed() {
+		assert_eq!(csv_field("plain"), "plain");
+		assert_eq!(csv_field(r#"{"a":1,"b":"x"}"#), r#""{""a"":1,""b"":""x""}""#);
+	}
+}
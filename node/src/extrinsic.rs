@@ -0,0 +1,320 @@
+//! Building and signing transactions for the node template runtime.
+//!
+//! [`SignedExtrinsicBuilder`] collects everything a signed transaction commits to: the signer,
+//! nonce, tip and era, and the genesis hash and runtime version of the chain. It is shared by
+//! the benchmarks and the `tx` and `load-test` subcommands.
+
+use codec::Encode;
+use node_template_runtime::{
+	self as runtime, opaque::Block, AccountId, Balance, BlockNumber, Hash, Index,
+};
+use sp_api::{Core, ProvideRuntimeApi, RuntimeVersion};
+use sp_blockchain::HeaderBackend;
+use sp_core::{sr25519, Pair};
+use sp_runtime::generic::{BlockId, Era};
+
+/// The longest period [`SignedExtrinsicBuilder::mortal`] accepts.
+///
+/// Eras of up to this many blocks start exactly at the block they are created for, so their
+/// hash is the best block hash.
+pub const MAX_MORTAL_PERIOD: u64 = 4096;
+
+/// Chain data that signed transactions commit to, besides the era.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainContext {
+	/// Hash of the genesis block.
+	pub genesis_hash: Hash,
+	/// `spec_version` of the runtime that will validate the transaction.
+	pub spec_version: u32,
+	/// `transaction_version` of the runtime that will validate the transaction.
+	pub transaction_version: u32,
+}
+
+impl ChainContext {
+	/// The context of a chain with the given genesis running the runtime `version`.
+	pub fn new(genesis_hash: Hash, version: &RuntimeVersion) -> Self {
+		Self {
+			genesis_hash,
+			spec_version: version.spec_version,
+			transaction_version: version.transaction_version,
+		}
+	}
+}
+
+/// Builds transactions signed by one account.
+///
+/// Transactions are mortal by default, valid for [`default_mortality`] blocks starting at the best
+/// block the builder was created for.
+#[derive(Clone)]
+pub struct SignedExtrinsicBuilder {
+	signer: sr25519::Pair,
+	context: ChainContext,
+	best_hash: Hash,
+	best_number: BlockNumber,
+	nonce: Index,
+	tip: Balance,
+	mortality: Option<u64>,
+}
+
+impl SignedExtrinsicBuilder {
+	/// A builder for transactions of `signer` on the chain described by `context`, whose best
+	/// block is `best_number` with hash `best_hash`.
+	pub fn new(
+		signer: sr25519::Pair,
+		context: ChainContext,
+		best_hash: Hash,
+		best_number: BlockNumber,
+	) -> Self {
+		Self {
+			signer,
+			context,
+			best_hash,
+			best_number,
+			nonce: 0,
+			tip: 0,
+			mortality: Some(default_mortality()),
+		}
+	}
+
+	/// A builder for transactions of `signer`, taking the genesis hash, best block and runtime
+	/// version from `client`.
+	pub fn from_client<C>(client: &C, signer: sr25519::Pair) -> Result<Self, String>
+	where
+		C: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+		C::Api: Core<Block>,
+	{
+		let genesis_hash = client
+			.hash(0)
+			.map_err(|e| format!("Failed to read the genesis hash: {}", e))?
+			.ok_or("Genesis block not found")?;
+		let info = client.info();
+		let version = client
+			.runtime_api()
+			.version(&BlockId::Hash(info.best_hash))
+			.map_err(|e| format!("Failed to read the runtime version: {}", e))?;
+
+		Ok(Self::new(
+			signer,
+			ChainContext::new(genesis_hash, &version),
+			info.best_hash,
+			info.best_number,
+		))
+	}
+
+	/// Use `nonce` for the transaction.
+	pub fn nonce(mut self, nonce: Index) -> Self {
+		self.nonce = nonce;
+		self
+	}
+
+	/// Pay `tip` to the block author on top of the fee.
+	pub fn tip(mut self, tip: Balance) -> Self {
+		self.tip = tip;
+		self
+	}
+
+	/// Make the transaction valid for `period` blocks from the best block.
+	///
+	/// The period is rounded up to a power of two and capped at [`MAX_MORTAL_PERIOD`].
+	pub fn mortal(mut self, period: u64) -> Self {
+		self.mortality = Some(period.min(MAX_MORTAL_PERIOD));
+		self
+	}
+
+	/// Make the transaction valid forever, or until the nonce is used.
+	pub fn immortal(mut self) -> Self {
+		self.mortality = None;
+		self
+	}
+
+	/// The account that signs the transactions.
+	pub fn account(&self) -> AccountId {
+		self.signer.public().into()
+	}
+
+	/// Sign `call`.
+	pub fn sign(&self, call: impl Into<runtime::Call>) -> runtime::UncheckedExtrinsic {
+		let era = match self.mortality {
+			Some(period) => Era::mortal(period, u64::from(self.best_number)),
+			None => Era::Immortal,
+		};
+		sign_extrinsic(
+			&self.signer,
+			call.into(),
+			self.nonce,
+			self.tip,
+			era,
+			self.best_hash,
+			&self.context,
+		)
+	}
+}
+
+/// The mortality of transactions built by [`SignedExtrinsicBuilder`] unless set otherwise: about
+/// half of the block hashes the runtime keeps.
+pub fn default_mortality() -> u64 {
+	let period = runtime::BlockHashCount::get()
+		.checked_next_power_of_two()
+		.map(|c| c / 2)
+		.unwrap_or(2) as u64;
+	period.min(MAX_MORTAL_PERIOD)
+}
+
+/// Sign `call` as `sender` for the chain described by `context`.
+///
+/// A mortal `era` must have been created for the block `era_hash`, which is ignored for
+/// immortal transactions.
+pub fn sign_extrinsic(
+	sender: &sr25519::Pair,
+	call: runtime::Call,
+	nonce: Index,
+	tip: Balance,
+	era: Era,
+	era_hash: Hash,
+	context: &ChainContext,
+) -> runtime::UncheckedExtrinsic {
+	let raw_payload = signed_payload(call, nonce, tip, era, era_hash, context);
+	let signature = raw_payload.using_encoded(|e| sender.sign(e));
+	let (call, extra, _) = raw_payload.deconstruct();
+
+	runtime::UncheckedExtrinsic::new_signed(
+		call,
+		AccountId::from(sender.public()).into(),
+		runtime::Signature::Sr25519(signature),
+		extra,
+	)
+}
+
+/// The payload a transaction of `call` signs, for signers that do not hold their key in memory.
+///
+/// Arguments are as for [`sign_extrinsic`].
+pub fn signed_payload(
+	call: runtime::Call,
+	nonce: Index,
+	tip: Balance,
+	era: Era,
+	era_hash: Hash,
+	context: &ChainContext,
+) -> runtime::SignedPayload {
+	let extra: runtime::SignedExtra = (
+		frame_system::CheckNonZeroSender::<runtime::Runtime>::new(),
+		frame_system::CheckSpecVersion::<runtime::Runtime>::new(),
+		frame_system::CheckTxVersion::<runtime::Runtime>::new(),
+		frame_system::CheckGenesis::<runtime::Runtime>::new(),
+		frame_system::CheckEra::<runtime::Runtime>::from(era),
+		runtime::pallet_allowlist::CheckAllowlist::<runtime::Runtime>::new(),
+		frame_system::CheckNonce::<runtime::Runtime>::from(nonce),
+		frame_system::CheckWeight::<runtime::Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<runtime::Runtime>::from(tip),
+	);
+
+	runtime::SignedPayload::from_raw(
+		call,
+		extra,
+		(
+			(),
+			context.spec_version,
+			context.transaction_version,
+			context.genesis_hash,
+			if era.is_immortal() { context.genesis_hash } else { era_hash },
+			(),
+			(),
+			(),
+			(),
+		),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::{
+		AllowlistConfig, BalancesConfig, BuildStorage, Executive, GenesisConfig, SystemCall,
+		VERSION,
+	};
+	use sp_keyring::Sr25519Keyring;
+	use sp_runtime::transaction_validity::{
+		InvalidTransaction, TransactionSource, TransactionValidity,
+	};
+
+	/// The hash `frame_system` stores for the genesis block when building genesis storage.
+	const GENESIS_HASH: [u8; 32] = [69; 32];
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		GenesisConfig {
+			balances: BalancesConfig {
+				balances: vec![(Sr25519Keyring::Alice.to_account_id(), 1 << 60)],
+			},
+			allowlist: AllowlistConfig {
+				accounts: vec![
+					Sr25519Keyring::Alice.to_account_id(),
+					Sr25519Keyring::Bob.to_account_id(),
+				],
+			},
+			..Default::default()
+		}
+		.build_storage()
+		.unwrap()
+		.into()
+	}
+
+	fn alice() -> SignedExtrinsicBuilder {
+		let context = ChainContext::new(GENESIS_HASH.into(), &VERSION);
+		SignedExtrinsicBuilder::new(Sr25519Keyring::Alice.pair(), context, GENESIS_HASH.into(), 0)
+	}
+
+	fn validate(xt: runtime::UncheckedExtrinsic) -> TransactionValidity {
+		new_test_ext().execute_with(|| {
+			Executive::validate_transaction(TransactionSource::External, xt, GENESIS_HASH.into())
+		})
+	}
+
+	fn remark() -> SystemCall {
+		SystemCall::remark { remark: b"hello".to_vec() }
+	}
+
+	#[test]
+	fn signed_transactions_are_valid() {
+		assert!(validate(alice().sign(remark())).is_ok());
+		assert!(validate(alice().immortal().sign(remark())).is_ok());
+		assert!(validate(alice().mortal(16).tip(1_000).sign(remark())).is_ok());
+	}
+
+	#[test]
+	fn nonce_is_signed() {
+		let validity = validate(alice().nonce(2).sign(remark())).unwrap();
+		// Nonce 2 waits for the transactions with nonces 0 and 1.
+		assert_eq!(validity.requires, vec![(Sr25519Keyring::Alice.to_account_id(), 1u32).encode()]);
+	}
+
+	#[test]
+	fn signature_covers_the_chain_context() {
+		let mut wrong_genesis = alice();
+		wrong_genesis.context.genesis_hash = Hash::repeat_byte(1);
+		let mut wrong_version = alice();
+		wrong_version.context.spec_version += 1;
+		let mut wrong_era_hash = alice();
+		wrong_era_hash.best_hash = Hash::repeat_byte(1);
+
+		for builder in [wrong_genesis, wrong_version, wrong_era_hash] {
+			assert_eq!(validate(builder.sign(remark())), Err(InvalidTransaction::BadProof.into()));
+		}
+	}
+
+	#[test]
+	fn unfunded_signers_cannot_pay() {
+		let mut bob = alice();
+		bob.signer = Sr25519Keyring::Bob.pair();
+		assert_eq!(validate(bob.sign(remark())), Err(InvalidTransaction::Payment.into()));
+	}
+
+	#[test]
+	fn signers_must_be_allowlisted() {
+		let mut charlie = alice();
+		charlie.signer = Sr25519Keyring::Charlie.pair();
+		assert_eq!(
+			validate(charlie.sign(remark())),
+			Err(InvalidTransaction::Custom(runtime::pallet_allowlist::NOT_ALLOWLISTED).into())
+		);
+	}
+}
@@ -0,0 +1,222 @@
+//! HTTP health and readiness endpoints for orchestrators.
+//!
+//! - `GET /health` answers `200` unless finality has stalled, i.e. the finalized block lags the
+//!   best block by more than `--max-finality-lag` blocks. The lag is not checked during a major
+//!   sync, when it is expected to be large.
+//! - `GET /ready` answers `200` only if the node is also done syncing and, on chains other than
+//!   development ones, connected to peers.
+//!
+//! Both return the node status as JSON, with `503` when the check fails: the peer count, whether
+//! the node is syncing, the best and finalized block numbers, the gap between them, and whether
+//! the node is authoring.
+
+use hyper::{
+	service::{make_service_fn, service_fn},
+	Body, Method, Request, Response, Server, StatusCode,
+};
+use std::{
+	convert::Infallible,
+	net::{Ipv4Addr, SocketAddr},
+	sync::Arc,
+};
+
+/// Command line options of the health endpoint.
+#[derive(Debug, Clone, clap::Args)]
+pub struct HealthParams {
+	/// Serve `/health` and `/ready` on this port. Disabled if not given.
+	#[clap(long, value_name = "PORT")]
+	pub health_port: Option<u16>,
+
+	/// Serve the health endpoint on all interfaces instead of only on localhost.
+	#[clap(long)]
+	pub health_external: bool,
+
+	/// Number of blocks the finalized block may lag behind the best block before `/health`
+	/// reports finality as stalled. Ignored while the node is syncing.
+	#[clap(long, value_name = "BLOCKS", default_value_t = 32)]
+	pub max_finality_lag: u32,
+}
+
+impl HealthParams {
+	/// The endpoint configuration, if it is enabled.
+	pub fn config(&self) -> Option<HealthConfig> {
+		let ip = if self.health_external { Ipv4Addr::UNSPECIFIED } else { Ipv4Addr::LOCALHOST };
+		self.health_port.map(|port| HealthConfig {
+			addr: SocketAddr::new(ip.into(), port),
+			max_finality_lag: self.max_finality_lag,
+		})
+	}
+}
+
+/// Configuration of the health endpoint.
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+	/// Address to listen on.
+	pub addr: SocketAddr,
+	/// See [`HealthParams::max_finality_lag`].
+	pub max_finality_lag: u32,
+}
+
+/// What the endpoints report about the node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+	/// Number of connected peers.
+	pub peers: usize,
+	/// Whether the node is catching up with the chain.
+	pub is_syncing: bool,
+	/// Whether the node is expected to have peers, i.e. the chain is not a development chain.
+	pub should_have_peers: bool,
+	/// Number of the best block.
+	pub best: u32,
+	/// Number of the last finalized block.
+	pub finalized: u32,
+	/// Whether the node holds the key of a current block author.
+	pub is_authoring: bool,
+}
+
+impl Status {
+	fn finality_lag(&self) -> u32 {
+		self.best.saturating_sub(self.finalized)
+	}
+
+	fn is_healthy(&self, max_finality_lag: u32) -> bool {
+		// While syncing, the best block races ahead of the finality proofs the node has fetched,
+		// so the lag says nothing about the chain.
+		self.is_syncing || self.finality_lag() <= max_finality_lag
+	}
+
+	fn is_ready(&self, max_finality_lag: u32) -> bool {
+		self.is_healthy(max_finality_lag) &&
+			!self.is_syncing &&
+			(self.peers > 0 || !self.should_have_peers)
+	}
+
+	fn to_json(&self) -> String {
+		format!(
+			concat!(
+				r#"{{"peers":{},"is_syncing":{},"best":{},"finalized":{},"#,
+				r#""finality_lag":{},"is_authoring":{}}}"#,
+			),
+			self.peers,
+			self.is_syncing,
+			self.best,
+			self.finalized,
+			self.finality_lag(),
+			self.is_authoring,
+		)
+	}
+}
+
+/// Reads the current [`Status`] of the node.
+pub type StatusSource = Arc<dyn Fn() -> Status + Send + Sync>;
+
+/// Bind the endpoint to `config.addr`. The returned future serves requests until the node stops.
+pub fn serve(
+	config: HealthConfig,
+	status: StatusSource,
+) -> Result<impl std::future::Future<Output = ()>, String> {
+	let max_finality_lag = config.max_finality_lag;
+	let make_service = make_service_fn(move |_| {
+		let status = status.clone();
+		async move {
+			Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+				let response = respond(&request, &status(), max_finality_lag);
+				async move { Ok::<_, Infallible>(response) }
+			}))
+		}
+	});
+	let server = Server::try_bind(&config.addr)
+		.map_err(|e| format!("Failed to bind the health endpoint to {}: {}", config.addr, e))?
+		.serve(make_service);
+	log::info!("Health endpoint listening on http://{}", config.addr);
+
+	Ok(async move {
+		if let Err(e) = server.await {
+			log::error!("Health endpoint failed: {}", e);
+		}
+	})
+}
+
+fn respond(request: &Request<Body>, status: &Status, max_finality_lag: u32) -> Response<Body> {
+	let ok = match (request.method(), request.uri().path()) {
+		(&Method::GET, "/health") => status.is_healthy(max_finality_lag),
+		(&Method::GET, "/ready") => status.is_ready(max_finality_lag),
+		_ => {
+			let mut response = Response::new(Body::empty());
+			*response.status_mut() = StatusCode::NOT_FOUND;
+			return response
+		},
+	};
+
+	let mut response = Response::new(Body::from(status.to_json()));
+	*response.status_mut() = if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+	response
+		.headers_mut()
+		.insert(hyper::header::CONTENT_TYPE, "application/json".parse().expect("valid header"));
+	response
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn status() -> Status {
+		Status {
+			peers: 2,
+			is_syncing: false,
+			should_have_peers: true,
+			best: 100,
+			finalized: 98,
+			is_authoring: true,
+		}
+	}
+
+	fn code(path: &str, status: &Status) -> StatusCode {
+		let request = Request::get(path).body(Body::empty()).unwrap();
+		respond(&request, status, 10).status()
+	}
+
+	#[test]
+	fn synced_node_is_healthy_and_ready() {
+		assert_eq!(code("/health", &status()), StatusCode::OK);
+		assert_eq!(code("/ready", &status()), StatusCode::OK);
+		assert_eq!(code("/metrics", &status()), StatusCode::NOT_FOUND);
+	}
+
+	#[test]
+	fn syncing_node_is_healthy_but_not_ready() {
+		let syncing = Status { is_syncing: true, ..status() };
+		assert_eq!(code("/health", &syncing), StatusCode::OK);
+		assert_eq!(code("/ready", &syncing), StatusCode::SERVICE_UNAVAILABLE);
+
+		let catching_up = Status { finalized: 0, ..syncing };
+		assert_eq!(code("/health", &catching_up), StatusCode::OK);
+		assert_eq!(code("/ready", &catching_up), StatusCode::SERVICE_UNAVAILABLE);
+
+		let alone = Status { peers: 0, ..status() };
+		assert_eq!(code("/ready", &alone), StatusCode::SERVICE_UNAVAILABLE);
+		let dev = Status { should_have_peers: false, ..alone };
+		assert_eq!(code("/ready", &dev), StatusCode::OK);
+	}
+
+	#[test]
+	fn stalled_finality_is_unhealthy() {
+		let at_limit = Status { finalized: 90, ..status() };
+		assert_eq!(code("/health", &at_limit), StatusCode::OK);
+
+		let stalled = Status { finalized: 89, ..status() };
+		assert_eq!(code("/health", &stalled), StatusCode::SERVICE_UNAVAILABLE);
+		assert_eq!(code("/ready", &stalled), StatusCode::SERVICE_UNAVAILABLE);
+	}
+
+	#[test]
+	fn status_is_reported_as_json() {
+		assert_eq!(
+			status().to_json(),
+			concat!(
+				r#"{"peers":2,"is_syncing":false,"best":100,"finalized":98,"#,
+				r#""finality_lag":2,"is_authoring":true}"#,
+			)
+		);
+	}
+}
@@ -1,3 +1,24 @@
+//! Substrate Node Template CLI library.
+
+pub mod benchmarking;
 pub mod chain_spec;
+pub mod cli;
+pub mod command;
+pub mod decode;
+pub mod events;
+pub mod export_decoded;
+pub mod extrinsic;
+pub mod health;
+pub mod load_test;
+pub mod metadata_diff;
+pub mod metrics;
+pub mod node_authorization;
 pub mod rpc;
 pub mod service;
+pub mod submit;
+pub mod testing;
+#[cfg(feature = "try-runtime")]
+pub mod try_state;
+pub mod tx;
+pub mod upgrade_check;
+pub mod wasm_runtime;
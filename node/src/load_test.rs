@@ -0,0 +1,416 @@
+//! The `load-test` subcommand: flood a node with pre-signed transactions from many accounts and
+//! report how they are included.
+
+use crate::{
+	extrinsic::{ChainContext, SignedExtrinsicBuilder},
+	tx::request,
+};
+use codec::{Decode, Encode};
+use frame_support::storage::StorageMap;
+use jsonrpsee::{
+	core::client::{Subscription, SubscriptionClientT},
+	rpc_params,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use node_template_runtime::{
+	self as runtime, opaque, pallet_allowlist, AccountId, AllowlistCall, Balance, BalancesCall,
+	BlockNumber, Hash, Header, Index, Runtime, SudoCall, SystemCall, TemplateCall,
+	EXISTENTIAL_DEPOSIT,
+};
+use sc_cli::RuntimeVersion;
+use sp_core::{blake2_256, sr25519, twox_128, Bytes, Pair};
+use sp_runtime::{generic::SignedBlock, traits::Header as _};
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+/// Submit pre-signed transactions to a node at a fixed rate and report inclusion latency,
+/// dropped transactions and throughput per block.
+///
+/// Transactions count as included once their block is finalized, so that blocks skipped by the
+/// best chain or lost in a reorg are neither missed nor counted.
+///
+/// Transactions are signed by the accounts `<funder>//0`, `<funder>//1`, ..., which are funded
+/// and added to the allowlist by the funder first. The funder must therefore be on the allowlist
+/// and hold the sudo key, which is checked before anything is sent.
+#[derive(Debug, clap::Parser)]
+pub struct LoadTestCmd {
+	/// Pallet of the extrinsic to send: `system`, `balances` or `template`.
+	#[clap(long, default_value = "system")]
+	pub pallet: String,
+
+	/// Extrinsic to send: `remark`, `transfer_keep_alive` or `do_something`.
+	#[clap(long, default_value = "remark")]
+	pub extrinsic: String,
+
+	/// Total number of transactions to send.
+	#[clap(long, default_value = "1000")]
+	pub count: u32,
+
+	/// Number of derived accounts to spread the transactions over.
+	#[clap(long, default_value = "100")]
+	pub accounts: u32,
+
+	/// Transactions submitted per second.
+	#[clap(long, default_value = "50")]
+	pub tps: u32,
+
+	/// Secret URI of the account that funds the derived accounts, e.g. `//Alice` on a dev chain.
+	#[clap(long)]
+	pub funder: String,
+
+	/// Amount given to each derived account before the test.
+	#[clap(long, default_value_t = 1_000_000_000 * EXISTENTIAL_DEPOSIT)]
+	pub fund: Balance,
+
+	/// Seconds to wait for inclusion after the last submission before counting the remaining
+	/// transactions as dropped.
+	#[clap(long, default_value = "60")]
+	pub timeout: u64,
+
+	/// Websocket endpoint of the node.
+	#[clap(long, default_value = "ws://127.0.0.1:9944")]
+	pub url: String,
+}
+
+/// The call sent by the test for `pallet` and `extrinsic`. Transfers go to `dest`.
+fn load_call(pallet: &str, extrinsic: &str, dest: &AccountId) -> Result<runtime::Call, String> {
+	match (pallet, extrinsic) {
+		("system", "remark") => Ok(SystemCall::remark { remark: vec![] }.into()),
+		("balances", "transfer_keep_alive") => Ok(BalancesCall::transfer_keep_alive {
+			dest: dest.clone().into(),
+			value: EXISTENTIAL_DEPOSIT,
+		}
+		.into()),
+		("template", "do_something") => Ok(TemplateCall::do_something { something: 1 }.into()),
+		_ => Err(format!("Unknown extrinsic {}::{}", pallet, extrinsic)),
+	}
+}
+
+/// Signs as `pair` with immortal transactions, so that transactions signed up front do not expire
+/// during long runs.
+fn immortal_signer(pair: &sr25519::Pair, context: ChainContext) -> SignedExtrinsicBuilder {
+	// The best block only matters for mortal transactions.
+	SignedExtrinsicBuilder::new(pair.clone(), context, context.genesis_hash, 0).immortal()
+}
+
+/// A transaction sent by the test.
+struct Sent {
+	submitted: Instant,
+	included: Option<(Instant, u32)>,
+}
+
+/// What the block watcher has seen so far.
+#[derive(Default)]
+struct Observed {
+	sent: HashMap<Hash, Sent>,
+	/// Number of the test's transactions included per block.
+	per_block: BTreeMap<u32, usize>,
+}
+
+impl Observed {
+	fn pending(&self) -> usize {
+		self.sent.values().filter(|s| s.included.is_none()).count()
+	}
+}
+
+impl LoadTestCmd {
+	/// Run the test and print the report.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		if self.accounts == 0 || self.tps == 0 {
+			return Err("`--accounts` and `--tps` must be greater than zero".into())
+		}
+		let funder = sr25519::Pair::from_string(&self.funder, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+
+		let rt = tokio::runtime::Builder::new_multi_thread()
+			.enable_all()
+			.build()
+			.map_err(|e| format!("Failed to start the async runtime: {}", e))?;
+		rt.block_on(async {
+			let client = WsClientBuilder::default()
+				.build(&self.url)
+				.await
+				.map_err(|e| format!("Failed to connect to {}: {}", self.url, e))?;
+			Ok(self.run_with(Arc::new(client), funder).await?)
+		})
+	}
+
+	async fn run_with(&self, client: Arc<WsClient>, funder: sr25519::Pair) -> Result<(), String> {
+		let genesis_hash: Hash = request(&client, "chain_getBlockHash", rpc_params![0u32]).await?;
+		let version: RuntimeVersion =
+			request(&client, "state_getRuntimeVersion", rpc_params![]).await?;
+		let context = ChainContext::new(genesis_hash, &version);
+		let funder_id = AccountId::from(funder.public());
+		let call = load_call(&self.pallet, &self.extrinsic, &funder_id)?;
+		check_funder(&client, &funder_id).await?;
+
+		let accounts = (0..self.accounts)
+			.map(|n| sr25519::Pair::from_string(&format!("{}//{}", self.funder, n), None))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| format!("Failed to derive accounts: {:?}", e))?;
+		self.fund(&client, &funder, &accounts, &context).await?;
+
+		println!("Signing {} transactions", self.count);
+		let mut signers = Vec::with_capacity(accounts.len());
+		let mut nonces = Vec::with_capacity(accounts.len());
+		for account in &accounts {
+			signers.push(immortal_signer(account, context));
+			nonces.push(next_index(&client, account).await?);
+		}
+		let mut transactions = Vec::with_capacity(self.count as usize);
+		for i in 0..self.count as usize {
+			let n = i % accounts.len();
+			let xt = signers[n].clone().nonce(nonces[n]).sign(call.clone());
+			transactions.push(xt.encode());
+			nonces[n] += 1;
+		}
+
+		let observed = Arc::new(Mutex::new(Observed::default()));
+		let watcher = tokio::spawn(watch_blocks(client.clone(), observed.clone()));
+
+		println!("Submitting at {} transactions per second", self.tps);
+		let started = Instant::now();
+		let mut rejected = BTreeMap::<String, usize>::new();
+		let mut ticks = tokio::time::interval(Duration::from_secs(1) / self.tps);
+		for xt in transactions {
+			ticks.tick().await;
+			let hash = Hash::from(blake2_256(&xt));
+			observed
+				.lock()
+				.expect("not poisoned")
+				.sent
+				.insert(hash, Sent { submitted: Instant::now(), included: None });
+			let result: Result<Hash, _> =
+				request(&client, "author_submitExtrinsic", rpc_params![Bytes(xt)]).await;
+			if let Err(e) = result {
+				observed.lock().expect("not poisoned").sent.remove(&hash);
+				*rejected.entry(e).or_default() += 1;
+			}
+		}
+		let submitted_in = started.elapsed();
+
+		let deadline = Instant::now() + Duration::from_secs(self.timeout);
+		while observed.lock().expect("not poisoned").pending() > 0 && Instant::now() < deadline {
+			tokio::time::sleep(Duration::from_millis(500)).await;
+		}
+		watcher.abort();
+
+		let observed = observed.lock().expect("not poisoned");
+		report(&observed, &rejected, started, submitted_in);
+		Ok(())
+	}
+
+	/// Give every account `self.fund` from `funder` and add it to the allowlist, waiting until
+	/// the transactions are in a finalized block.
+	async fn fund(
+		&self,
+		client: &WsClient,
+		funder: &sr25519::Pair,
+		accounts: &[sr25519::Pair],
+		context: &ChainContext,
+	) -> Result<(), String> {
+		println!("Funding and allowlisting {} accounts", accounts.len());
+		let mut nonce = next_index(client, funder).await?;
+		let funder = immortal_signer(funder, *context);
+		let mut last = None;
+		for account in accounts {
+			let who = AccountId::from(account.public());
+			let transfer =
+				BalancesCall::transfer_keep_alive { dest: who.clone().into(), value: self.fund };
+			// Accounts already on the allowlist fail the inner call, which is harmless.
+			let allow = SudoCall::sudo { call: Box::new(AllowlistCall::add { who }.into()) };
+			for call in [runtime::Call::from(transfer), allow.into()] {
+				let xt = funder.clone().nonce(nonce).sign(call);
+				let hash: Hash =
+					request(client, "author_submitExtrinsic", rpc_params![Bytes(xt.encode())])
+						.await?;
+				last = Some(hash);
+				nonce += 1;
+			}
+		}
+
+		// Transactions from the funder are included in nonce order, so once the last one is in a
+		// block all of them are.
+		if let Some(last) = last {
+			let observed = Arc::new(Mutex::new(Observed::default()));
+			observed
+				.lock()
+				.expect("not poisoned")
+				.sent
+				.insert(last, Sent { submitted: Instant::now(), included: None });
+			let deadline = Instant::now() + Duration::from_secs(self.timeout);
+			let mut heads = subscribe_finalized_heads(client).await?;
+			let mut last = None;
+			while observed.lock().expect("not poisoned").pending() > 0 {
+				if Instant::now() > deadline {
+					return Err("Timed out waiting for the accounts to be funded".into())
+				}
+				let header = next_head(&mut heads).await?;
+				last = Some(note_blocks_since(client, &observed, header, last).await?);
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Check that `funder` can sign transactions and allowlist the derived accounts.
+async fn check_funder(client: &WsClient, funder: &AccountId) -> Result<(), String> {
+	let key = pallet_allowlist::Allowlist::<Runtime>::hashed_key_for(funder);
+	let allowlisted: Option<Bytes> =
+		request(client, "state_getStorage", rpc_params![Bytes(key)]).await?;
+	if allowlisted.is_none() {
+		return Err(format!("The funder {} is not on the allowlist", funder))
+	}
+
+	let key = [twox_128(b"Sudo"), twox_128(b"Key")].concat();
+	let sudo_key: Option<Bytes> =
+		request(client, "state_getStorage", rpc_params![Bytes(key)]).await?;
+	let sudo_key = sudo_key
+		.map(|key| AccountId::decode(&mut &key[..]))
+		.transpose()
+		.map_err(|e| format!("Failed to decode the sudo key: {}", e))?;
+	if sudo_key.as_ref() != Some(funder) {
+		return Err(format!(
+			"The funder {} does not hold the sudo key, so it cannot allowlist the accounts",
+			funder
+		))
+	}
+	Ok(())
+}
+
+type Heads = Subscription<Header>;
+
+async fn subscribe_finalized_heads(client: &WsClient) -> Result<Heads, String> {
+	client
+		.subscribe(
+			"chain_subscribeFinalizedHeads",
+			rpc_params![],
+			"chain_unsubscribeFinalizedHeads",
+		)
+		.await
+		.map_err(|e| format!("Failed to subscribe to finalized heads: {}", e))
+}
+
+async fn next_head(heads: &mut Heads) -> Result<Header, String> {
+	match heads.next().await {
+		Some(Ok(header)) => Ok(header),
+		Some(Err(e)) => Err(format!("Finalized heads subscription failed: {}", e)),
+		None => Err("Finalized heads subscription closed".into()),
+	}
+}
+
+/// Record the inclusion of sent transactions in finalized blocks until aborted.
+async fn watch_blocks(client: Arc<WsClient>, observed: Arc<Mutex<Observed>>) -> Result<(), String> {
+	let mut heads = subscribe_finalized_heads(&client).await?;
+	let mut last = None;
+	loop {
+		let header = next_head(&mut heads).await?;
+		last = Some(note_blocks_since(&client, &observed, header, last).await?);
+	}
+}
+
+/// Mark the sent transactions in the block of `header`, and in its ancestors after block `last`,
+/// as included. Finality may advance by several blocks at once, and only the last one is
+/// notified. Returns the number of `header`.
+async fn note_blocks_since(
+	client: &WsClient,
+	observed: &Mutex<Observed>,
+	header: Header,
+	last: Option<BlockNumber>,
+) -> Result<BlockNumber, String> {
+	let head = *header.number();
+	let (mut hash, mut number) = (header.hash(), head);
+	loop {
+		let parent = note_block(client, observed, hash, number).await?;
+		match last {
+			Some(last) if number > last + 1 => {
+				hash = parent;
+				number -= 1;
+			},
+			_ => return Ok(head),
+		}
+	}
+}
+
+/// Mark the sent transactions in block `number` with `hash` as included. Returns the hash of its
+/// parent.
+async fn note_block(
+	client: &WsClient,
+	observed: &Mutex<Observed>,
+	hash: Hash,
+	number: BlockNumber,
+) -> Result<Hash, String> {
+	let now = Instant::now();
+	let block: SignedBlock<opaque::Block> =
+		request(client, "chain_getBlock", rpc_params![hash]).await?;
+
+	let mut observed = observed.lock().expect("not poisoned");
+	let mut included = 0;
+	for xt in &block.block.extrinsics {
+		let hash = Hash::from(blake2_256(&xt.encode()));
+		if let Some(sent) = observed.sent.get_mut(&hash) {
+			if sent.included.is_none() {
+				sent.included = Some((now, number));
+				included += 1;
+			}
+		}
+	}
+	if included > 0 {
+		*observed.per_block.entry(number).or_default() += included;
+	}
+	Ok(*block.block.header.parent_hash())
+}
+
+fn report(
+	observed: &Observed,
+	rejected: &BTreeMap<String, usize>,
+	started: Instant,
+	submitted_in: Duration,
+) {
+	let mut latencies: Vec<Duration> = observed
+		.sent
+		.values()
+		.filter_map(|s| s.included.map(|(at, _)| at - s.submitted))
+		.collect();
+	latencies.sort();
+	let included = latencies.len();
+	let dropped = observed.pending();
+
+	println!("Blocks:");
+	for (number, count) in &observed.per_block {
+		println!("  #{}: {} transactions", number, count);
+	}
+	println!(
+		"Submitted {} transactions in {:.1}s",
+		observed.sent.len(),
+		submitted_in.as_secs_f64()
+	);
+	for (reason, count) in rejected {
+		println!("Rejected on submission: {} x {}", count, reason);
+	}
+	println!("Included: {}, dropped: {}", included, dropped);
+
+	if included > 0 {
+		let percentile = |p: usize| latencies[(included - 1) * p / 100].as_secs_f64();
+		println!(
+			"Inclusion latency, until finalized: min {:.2}s, median {:.2}s, p95 {:.2}s, max {:.2}s",
+			percentile(0),
+			percentile(50),
+			percentile(95),
+			percentile(100)
+		);
+		let last = observed.sent.values().filter_map(|s| s.included.map(|(at, _)| at)).max();
+		if let Some(last) = last {
+			let elapsed = (last - started).as_secs_f64();
+			println!("Throughput: {:.1} transactions per second", included as f64 / elapsed);
+		}
+	}
+}
+
+async fn next_index(client: &WsClient, who: &sr25519::Pair) -> Result<Index, String> {
+	let who = AccountId::from(who.public()).to_string();
+	request(client, "system_accountNextIndex", rpc_params![who]).await
+}
@@ -1,14 +1,6 @@
-//! Substrate Node Template CLI library.
+//! Substrate Node Template CLI, a thin binary over the `node_template` library.
 #![warn(missing_docs)]
 
-mod chain_spec;
-#[macro_use]
-mod service;
-mod benchmarking;
-mod cli;
-mod command;
-mod rpc;
-
 fn main() -> sc_cli::Result<()> {
-	command::run()
+	node_template::command::run()
 }
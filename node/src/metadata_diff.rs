@@ -0,0 +1,463 @@
+//! Comparison of the metadata of two runtimes, to find changes that break existing users of the
+//! chain, and the `metadata-diff` subcommand that reports them for two runtime blobs.
+
+use crate::wasm_runtime::WasmRuntime;
+use frame_metadata::{
+	ExtrinsicMetadata, PalletMetadata, RuntimeMetadataV14, StorageEntryMetadata, StorageEntryType,
+};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, Variant};
+use std::{fmt, path::PathBuf};
+
+/// How far nested types are expanded when comparing them. Deeper types are compared by path.
+const MAX_TYPE_DEPTH: usize = 8;
+
+/// What a change breaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Impact {
+	/// Nothing breaks.
+	None,
+	/// Transactions encoded for the old runtime no longer decode to the same call, so the
+	/// `transaction_version` has to be bumped.
+	Transactions,
+	/// Breaks the chain or its users regardless of versioning.
+	Breaking,
+}
+
+/// A single difference between two runtimes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+	/// What the change breaks.
+	pub impact: Impact,
+	/// Description of the change.
+	pub message: String,
+}
+
+impl Change {
+	/// A change that breaks the chain or its users.
+	pub fn breaking(message: impl Into<String>) -> Self {
+		Self { impact: Impact::Breaking, message: message.into() }
+	}
+
+	/// A change to the encoding of transactions.
+	pub fn transactions(message: impl Into<String>) -> Self {
+		Self { impact: Impact::Transactions, message: message.into() }
+	}
+
+	/// A change worth knowing about that does not break anything.
+	pub fn note(message: impl Into<String>) -> Self {
+		Self { impact: Impact::None, message: message.into() }
+	}
+}
+
+impl fmt::Display for Change {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let kind = match self.impact {
+			Impact::None => "note",
+			Impact::Transactions => "transactions",
+			Impact::Breaking => "BREAKING",
+		};
+		write!(f, "{}: {}", kind, self.message)
+	}
+}
+
+/// Compare the metadata of the `old` runtime with the `new` one.
+///
+/// Changes to the layout of the storage entries named in `migrated`, as `Pallet::Entry`, are
+/// only noted, since a migration converts their values.
+pub fn diff(
+	old: &RuntimeMetadataV14,
+	new: &RuntimeMetadataV14,
+	migrated: &[String],
+) -> Vec<Change> {
+	let mut changes = Vec::new();
+	diff_extrinsic(&old.extrinsic, &new.extrinsic, &old.types, &new.types, &mut changes);
+	for old_pallet in &old.pallets {
+		match new.pallets.iter().find(|p| p.name == old_pallet.name) {
+			Some(new_pallet) => {
+				let pallet = Pallet {
+					old: old_pallet,
+					new: new_pallet,
+					old_types: &old.types,
+					new_types: &new.types,
+					migrated,
+				};
+				pallet.diff_index(&mut changes);
+				pallet.diff_calls(&mut changes);
+				pallet.diff_storage(&mut changes);
+			},
+			None if old_pallet.calls.is_some() => changes.push(Change::transactions(format!(
+				"pallet {} removed together with its calls",
+				old_pallet.name
+			))),
+			None => changes.push(Change::note(format!("pallet {} removed", old_pallet.name))),
+		}
+	}
+	for new_pallet in &new.pallets {
+		if !old.pallets.iter().any(|p| p.name == new_pallet.name) {
+			changes.push(Change::note(format!(
+				"pallet {} added at index {}",
+				new_pallet.name, new_pallet.index
+			)));
+		}
+	}
+	changes
+}
+
+/// Compare the extrinsic format, including the signed extensions, which are part of every signed
+/// transaction.
+fn diff_extrinsic(
+	old: &ExtrinsicMetadata<PortableForm>,
+	new: &ExtrinsicMetadata<PortableForm>,
+	old_types: &PortableRegistry,
+	new_types: &PortableRegistry,
+	changes: &mut Vec<Change>,
+) {
+	if old.version != new.version {
+		changes.push(Change::transactions(format!(
+			"extrinsic version changed from {} to {}",
+			old.version, new.version
+		)));
+	}
+
+	let old_ids: Vec<_> = old.signed_extensions.iter().map(|e| e.identifier.as_str()).collect();
+	let new_ids: Vec<_> = new.signed_extensions.iter().map(|e| e.identifier.as_str()).collect();
+	if old_ids != new_ids {
+		changes.push(Change::transactions(format!(
+			"signed extensions changed from ({}) to ({})",
+			old_ids.join(", "),
+			new_ids.join(", ")
+		)));
+		return
+	}
+	for (old_ext, new_ext) in old.signed_extensions.iter().zip(&new.signed_extensions) {
+		let old_ty = describe(old_types, old_ext.ty.id());
+		let new_ty = describe(new_types, new_ext.ty.id());
+		if old_ty != new_ty {
+			changes.push(Change::transactions(format!(
+				"signed extension {} changed type from `{}` to `{}`",
+				old_ext.identifier, old_ty, new_ty
+			)));
+		}
+		let old_signed = describe(old_types, old_ext.additional_signed.id());
+		let new_signed = describe(new_types, new_ext.additional_signed.id());
+		if old_signed != new_signed {
+			changes.push(Change::transactions(format!(
+				"signed extension {} changed additional signed data from `{}` to `{}`",
+				old_ext.identifier, old_signed, new_signed
+			)));
+		}
+	}
+}
+
+/// The change required when `changes` alter the encoding of transactions but the
+/// `transaction_version` was not bumped from `old` to `new`.
+pub fn check_transaction_version(changes: &[Change], old: u32, new: u32) -> Option<Change> {
+	let affected = changes.iter().filter(|c| c.impact == Impact::Transactions).count();
+	(affected > 0 && new <= old).then(|| {
+		Change::breaking(format!(
+			"{} change(s) affect transactions but transaction_version was not bumped from {}",
+			affected, old
+		))
+	})
+}
+
+/// Report the changes between two runtime blobs, failing if any of them breaks the chain or its
+/// users.
+#[derive(Debug, clap::Parser)]
+pub struct MetadataDiffCmd {
+	/// The runtime currently in use.
+	#[clap(long, value_name = "FILE")]
+	pub old: PathBuf,
+
+	/// The runtime to compare with it.
+	#[clap(long, value_name = "FILE")]
+	pub new: PathBuf,
+
+	/// A storage entry, as `Pallet::Entry`, whose layout changes are converted by a migration of
+	/// the new runtime. May be given multiple times.
+	#[clap(long, value_name = "ENTRY")]
+	pub migrated: Vec<String>,
+}
+
+impl MetadataDiffCmd {
+	/// Run the comparison, printing every change found.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let old = WasmRuntime::load(&self.old)?;
+		let new = WasmRuntime::load(&self.new)?;
+		let (old_version, new_version) = (old.version()?, new.version()?);
+		println!("Comparing {} with {}", new_version, old_version);
+
+		let mut changes = diff(&old.metadata()?, &new.metadata()?, &self.migrated);
+		changes.extend(check_transaction_version(
+			&changes,
+			old_version.transaction_version,
+			new_version.transaction_version,
+		));
+		report(&changes)
+	}
+}
+
+/// Print `changes`, failing if any of them is breaking.
+pub fn report(changes: &[Change]) -> sc_cli::Result<()> {
+	for change in changes {
+		println!("{}", change);
+	}
+	let breaking = changes.iter().filter(|c| c.impact == Impact::Breaking).count();
+	if breaking > 0 {
+		return Err(format!("Found {} breaking change(s)", breaking).into())
+	}
+	println!("No breaking changes found");
+	Ok(())
+}
+
+/// The same pallet in the old and the new runtime.
+struct Pallet<'a> {
+	old: &'a PalletMetadata<PortableForm>,
+	new: &'a PalletMetadata<PortableForm>,
+	old_types: &'a PortableRegistry,
+	new_types: &'a PortableRegistry,
+	migrated: &'a [String],
+}
+
+impl Pallet<'_> {
+	fn diff_index(&self, changes: &mut Vec<Change>) {
+		if self.old.index != self.new.index {
+			changes.push(Change::transactions(format!(
+				"pallet {} moved from index {} to {}",
+				self.old.name, self.old.index, self.new.index
+			)));
+		}
+	}
+
+	fn diff_calls(&self, changes: &mut Vec<Change>) {
+		let old_calls =
+			self.old.calls.as_ref().map_or(&[][..], |c| calls(self.old_types, c.ty.id()));
+		let new_calls =
+			self.new.calls.as_ref().map_or(&[][..], |c| calls(self.new_types, c.ty.id()));
+
+		for old_call in old_calls {
+			let name = format!("{}::{}", self.old.name, old_call.name());
+			let new_call = match new_calls.iter().find(|c| c.name() == old_call.name()) {
+				Some(call) => call,
+				None => {
+					changes.push(Change::transactions(format!("call {} removed", name)));
+					continue
+				},
+			};
+			if old_call.index() != new_call.index() {
+				changes.push(Change::transactions(format!(
+					"call {} moved from index {} to {}",
+					name,
+					old_call.index(),
+					new_call.index()
+				)));
+			}
+			let old_args = arguments(self.old_types, old_call);
+			let new_args = arguments(self.new_types, new_call);
+			if old_args != new_args {
+				changes.push(Change::transactions(format!(
+					"call {} changed arguments from ({}) to ({})",
+					name,
+					old_args.join(", "),
+					new_args.join(", ")
+				)));
+			}
+		}
+		for new_call in new_calls {
+			if !old_calls.iter().any(|c| c.name() == new_call.name()) {
+				changes.push(Change::note(format!(
+					"call {}::{} added at index {}",
+					self.new.name,
+					new_call.name(),
+					new_call.index()
+				)));
+			}
+		}
+	}
+
+	fn diff_storage(&self, changes: &mut Vec<Change>) {
+		let old_storage = match &self.old.storage {
+			Some(storage) => storage,
+			None => return,
+		};
+		let new_storage = &self.new.storage;
+
+		for old_entry in &old_storage.entries {
+			let name = format!("{}::{}", self.old.name, old_entry.name);
+			let new_entry =
+				new_storage.iter().flat_map(|s| &s.entries).find(|e| e.name == old_entry.name);
+			let new_entry = match new_entry {
+				Some(entry) => entry,
+				None => {
+					changes.push(Change::note(format!("storage {} removed", name)));
+					continue
+				},
+			};
+
+			let mut layout_changes = Vec::new();
+			if hashers(old_entry) != hashers(new_entry) {
+				layout_changes.push(format!(
+					"storage {} changed hashers from {:?} to {:?}",
+					name,
+					hashers(old_entry),
+					hashers(new_entry)
+				));
+			}
+			let old_ty = storage_type(self.old_types, &old_entry.ty);
+			let new_ty = storage_type(self.new_types, &new_entry.ty);
+			if old_ty != new_ty {
+				layout_changes.push(format!(
+					"storage {} changed type from `{}` to `{}`",
+					name, old_ty, new_ty
+				));
+			}
+
+			let migrated = self.migrated.contains(&name);
+			changes.extend(layout_changes.into_iter().map(|message| {
+				if migrated {
+					Change::note(format!("{}, converted by a migration", message))
+				} else {
+					Change::breaking(format!(
+						"{} without a migration, pass `--migrated {}` once one converts it",
+						message, name
+					))
+				}
+			}));
+		}
+	}
+}
+
+/// The hashers of a storage map, empty for plain storage values.
+fn hashers(entry: &StorageEntryMetadata<PortableForm>) -> Vec<String> {
+	match &entry.ty {
+		StorageEntryType::Plain(_) => Vec::new(),
+		StorageEntryType::Map { hashers, .. } =>
+			hashers.iter().map(|hasher| format!("{:?}", hasher)).collect(),
+	}
+}
+
+/// Names and types of the arguments of `call`.
+fn arguments(types: &PortableRegistry, call: &Variant<PortableForm>) -> Vec<String> {
+	call.fields()
+		.iter()
+		.map(|f| {
+			format!("{}: {}", f.name().map_or("_", |n| n.as_str()), describe(types, f.ty().id()))
+		})
+		.collect()
+}
+
+/// The variants of the call enum `id`.
+fn calls(types: &PortableRegistry, id: u32) -> &[Variant<PortableForm>] {
+	match types.resolve(id).map(|ty| ty.type_def()) {
+		Some(TypeDef::Variant(variants)) => variants.variants(),
+		_ => &[],
+	}
+}
+
+/// Structural description of the key and value types of a storage entry.
+fn storage_type(types: &PortableRegistry, ty: &StorageEntryType<PortableForm>) -> String {
+	match ty {
+		StorageEntryType::Plain(value) => describe(types, value.id()),
+		StorageEntryType::Map { key, value, .. } =>
+			format!("map {} => {}", describe(types, key.id()), describe(types, value.id())),
+	}
+}
+
+/// Describe the type `id` by its structure, so that types from different registries can be
+/// compared.
+pub fn describe(types: &PortableRegistry, id: u32) -> String {
+	let mut stack = Vec::new();
+	describe_at(types, id, &mut stack)
+}
+
+fn describe_at(types: &PortableRegistry, id: u32, stack: &mut Vec<u32>) -> String {
+	let ty = match types.resolve(id) {
+		Some(ty) => ty,
+		None => return format!("<unknown type {}>", id),
+	};
+	let path = ty.path().segments().join("::");
+	// Recursive types and deeply nested ones are only compared by name.
+	if stack.contains(&id) || stack.len() >= MAX_TYPE_DEPTH {
+		return path
+	}
+
+	stack.push(id);
+	let mut nested = |id: u32| describe_at(types, id, stack);
+	let body = match ty.type_def() {
+		TypeDef::Composite(composite) => {
+			let fields: Vec<_> = composite
+				.fields()
+				.iter()
+				.map(|f| match f.name() {
+					Some(name) => format!("{}: {}", name, nested(f.ty().id())),
+					None => nested(f.ty().id()),
+				})
+				.collect();
+			format!("{{{}}}", fields.join(", "))
+		},
+		TypeDef::Variant(variant) => {
+			let variants: Vec<_> = variant
+				.variants()
+				.iter()
+				.map(|v| {
+					let fields: Vec<_> = v.fields().iter().map(|f| nested(f.ty().id())).collect();
+					format!("{}({})", v.name(), fields.join(", "))
+				})
+				.collect();
+			variants.join(" | ")
+		},
+		TypeDef::Sequence(sequence) => format!("[{}]", nested(sequence.type_param().id())),
+		TypeDef::Array(array) => format!("[{}; {}]", nested(array.type_param().id()), array.len()),
+		TypeDef::Tuple(tuple) => {
+			let fields: Vec<_> = tuple.fields().iter().map(|f| nested(f.id())).collect();
+			format!("({})", fields.join(", "))
+		},
+		TypeDef::Primitive(primitive) => format!("{:?}", primitive).to_lowercase(),
+		TypeDef::Compact(compact) => format!("Compact<{}>", nested(compact.type_param().id())),
+		TypeDef::BitSequence(_) => "BitSequence".into(),
+	};
+	stack.pop();
+
+	if path.is_empty() {
+		body
+	} else {
+		format!("{} {}", path, body)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Compact;
+	use frame_metadata::SignedExtensionMetadata;
+	use scale_info::meta_type;
+
+	fn metadata(signed_extensions: Vec<SignedExtensionMetadata>) -> RuntimeMetadataV14 {
+		let extrinsic = ExtrinsicMetadata { ty: meta_type::<()>(), version: 4, signed_extensions };
+		RuntimeMetadataV14::new(Vec::new(), extrinsic, meta_type::<()>())
+	}
+
+	fn extension(identifier: &'static str) -> SignedExtensionMetadata {
+		SignedExtensionMetadata {
+			identifier,
+			ty: meta_type::<Compact<u32>>(),
+			additional_signed: meta_type::<()>(),
+		}
+	}
+
+	#[test]
+	fn adding_a_signed_extension_affects_transactions() {
+		let old = metadata(vec![extension("CheckNonce")]);
+		let new = metadata(vec![extension("CheckAllowlist"), extension("CheckNonce")]);
+
+		let changes = diff(&old, &new, &[]);
+		assert_eq!(
+			changes,
+			vec![Change::transactions(
+				"signed extensions changed from (CheckNonce) to (CheckAllowlist, CheckNonce)"
+			)]
+		);
+		assert!(check_transaction_version(&changes, 1, 1).is_some());
+		assert_eq!(check_transaction_version(&changes, 1, 2), None);
+	}
+}
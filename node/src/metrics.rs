@@ -0,0 +1,320 @@
+//! Node-side Prometheus metrics about block production, finality, the transaction pool and the
+//! template pallet.
+//!
+//! The metrics are registered on the node's registry, which prefixes their names with
+//! `substrate_`:
+//!
+//! - `substrate_node_template_blocks_authored_total{authority}`: imported blocks per Aura author,
+//!   including blocks on forks.
+//! - `substrate_node_template_finality_lag_blocks`: best block number minus finalized block number.
+//! - `substrate_node_template_pool_rejections_total{reason}`: transactions the pool refused, by
+//!   `InvalidTransaction` reason, or by pool error for other refusals.
+//! - `substrate_node_template_template_events_total{event}`: template pallet events in imported
+//!   blocks, by event name.
+
+use crate::{events::block_events, service::FullClient};
+use futures::{future::FutureExt, stream, StreamExt};
+use node_template_runtime::{opaque::Block, Event};
+use parity_util_mem::{MallocSizeOf, MallocSizeOfOps};
+use prometheus_endpoint::{register, CounterVec, Gauge, Opts, PrometheusError, Registry, U64};
+use sc_client_api::{BlockImportNotification, BlockchainEvents};
+use sc_transaction_pool_api::{
+	error::{Error as PoolError, IntoPoolError},
+	ChainEvent, ImportNotificationStream, MaintainedTransactionPool, PoolFuture, PoolStatus,
+	ReadyTransactions, TransactionFor, TransactionPool, TransactionSource,
+	TransactionStatusStreamFor, TxHash,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::{sr25519::AuthoritySignature, AuraApi};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Header as _, NumberFor},
+};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+
+/// Metrics updated from block import and finality notifications.
+#[derive(Clone)]
+pub struct Metrics {
+	blocks_authored: CounterVec<U64>,
+	finality_lag: Gauge<U64>,
+	template_events: CounterVec<U64>,
+}
+
+impl Metrics {
+	/// Create the metrics and register them on `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			blocks_authored: register(
+				CounterVec::new(
+					Opts::new(
+						"node_template_blocks_authored_total",
+						"Number of imported blocks per Aura author",
+					),
+					&["authority"],
+				)?,
+				registry,
+			)?,
+			finality_lag: register(
+				Gauge::new(
+					"node_template_finality_lag_blocks",
+					"Number of blocks between the best and the finalized block",
+				)?,
+				registry,
+			)?,
+			template_events: register(
+				CounterVec::new(
+					Opts::new(
+						"node_template_template_events_total",
+						"Number of template pallet events in imported blocks",
+					),
+					&["event"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Count the author and the template events of an imported block.
+	fn on_import(&self, client: &FullClient, notification: &BlockImportNotification<Block>) {
+		let header = &notification.header;
+		let parent = BlockId::Hash(*header.parent_hash());
+		let slot = sc_consensus_aura::find_pre_digest::<Block, AuthoritySignature>(header);
+		let authorities = client.runtime_api().authorities(&parent);
+		match (slot, authorities) {
+			(Ok(slot), Ok(authorities)) if !authorities.is_empty() => {
+				let author = &authorities[(*slot % authorities.len() as u64) as usize];
+				self.blocks_authored.with_label_values(&[&author.to_string()]).inc();
+			},
+			(slot, authorities) => log::debug!(
+				target: "metrics",
+				"No author for block {:?}: slot {:?}, authorities {:?}",
+				notification.hash,
+				slot.map_err(|e| e.to_string()),
+				authorities.map_err(|e| e.to_string()),
+			),
+		}
+
+		match block_events(client, notification.hash) {
+			Ok(records) =>
+				for record in records {
+					if let Event::TemplateModule(event) = record.event {
+						let name = variant_name(&event);
+						self.template_events.with_label_values(&[&name]).inc();
+					}
+				},
+			Err(e) => log::debug!(target: "metrics", "{}", e),
+		}
+	}
+
+	fn update_finality_lag(&self, client: &FullClient) {
+		let info = client.info();
+		self.finality_lag
+			.set(info.best_number.saturating_sub(info.finalized_number).into());
+	}
+}
+
+/// Update `metrics` from the notifications of `client`, until the client shuts down.
+pub async fn run(client: Arc<FullClient>, metrics: Metrics) {
+	let imports = client.import_notification_stream().map(Some);
+	let finality = client.finality_notification_stream().map(|_| None);
+	let mut notifications = stream::select(imports, finality);
+
+	while let Some(notification) = notifications.next().await {
+		if let Some(imported) = notification {
+			metrics.on_import(&client, &imported);
+		}
+		metrics.update_finality_lag(&client);
+	}
+}
+
+/// The name of the enum variant `value`, taken from its `Debug` output.
+fn variant_name(value: &impl std::fmt::Debug) -> String {
+	let debug = format!("{:?}", value);
+	let end = debug.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(debug.len());
+	debug[..end].to_string()
+}
+
+/// The label of a transaction refused by the pool.
+fn rejection_reason(error: &PoolError) -> String {
+	match error {
+		// `Custom` codes are kept, as they tell apart the pallets' own checks.
+		PoolError::InvalidTransaction(invalid) => format!("{:?}", invalid),
+		other => variant_name(other),
+	}
+}
+
+/// A transaction pool that counts the transactions it refuses.
+pub struct MeteredPool<P> {
+	inner: Arc<P>,
+	rejections: Option<CounterVec<U64>>,
+}
+
+impl<P> MeteredPool<P> {
+	/// Wrap `inner`, registering the rejection counter on `registry` if there is one.
+	pub fn new(inner: Arc<P>, registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+		let rejections = registry
+			.map(|registry| {
+				register(
+					CounterVec::new(
+						Opts::new(
+							"node_template_pool_rejections_total",
+							"Number of transactions refused by the transaction pool",
+						),
+						&["reason"],
+					)?,
+					registry,
+				)
+			})
+			.transpose()?;
+		Ok(Self { inner, rejections })
+	}
+}
+
+/// Count `error` if it is a refusal, and hand it back.
+fn record<E: IntoPoolError + From<PoolError>>(rejections: &Option<CounterVec<U64>>, error: E) -> E {
+	let rejections = match rejections {
+		Some(rejections) => rejections,
+		None => return error,
+	};
+	match error.into_pool_error() {
+		Ok(error) => {
+			rejections.with_label_values(&[&rejection_reason(&error)]).inc();
+			error.into()
+		},
+		Err(error) => error,
+	}
+}
+
+impl<P: TransactionPool + 'static> TransactionPool for MeteredPool<P> {
+	type Block = P::Block;
+	type Hash = P::Hash;
+	type InPoolTransaction = P::InPoolTransaction;
+	type Error = P::Error;
+
+	fn submit_at(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		let rejections = self.rejections.clone();
+		self.inner
+			.submit_at(at, source, xts)
+			.map(move |results| {
+				let count = |e| record(&rejections, e);
+				results
+					.map(|results| results.into_iter().map(|r| r.map_err(count)).collect())
+					.map_err(count)
+			})
+			.boxed()
+	}
+
+	fn submit_one(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		let rejections = self.rejections.clone();
+		self.inner
+			.submit_one(at, source, xt)
+			.map(move |result| result.map_err(|e| record(&rejections, e)))
+			.boxed()
+	}
+
+	fn submit_and_watch(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		let rejections = self.rejections.clone();
+		self.inner
+			.submit_and_watch(at, source, xt)
+			.map(move |result| result.map_err(|e| record(&rejections, e)))
+			.boxed()
+	}
+
+	fn ready_at(
+		&self,
+		at: NumberFor<Self::Block>,
+	) -> Pin<
+		Box<
+			dyn futures::Future<
+					Output = Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>,
+				> + Send,
+		>,
+	> {
+		self.inner.ready_at(at)
+	}
+
+	fn ready(&self) -> Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send> {
+		self.inner.ready()
+	}
+
+	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.inner.remove_invalid(hashes)
+	}
+
+	fn status(&self) -> PoolStatus {
+		self.inner.status()
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		self.inner.import_notification_stream()
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		self.inner.on_broadcasted(propagations)
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		self.inner.hash_of(xt)
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.inner.ready_transaction(hash)
+	}
+}
+
+impl<P: MaintainedTransactionPool + 'static> MaintainedTransactionPool for MeteredPool<P> {
+	fn maintain(
+		&self,
+		event: ChainEvent<Self::Block>,
+	) -> Pin<Box<dyn futures::Future<Output = ()> + Send>> {
+		self.inner.maintain(event)
+	}
+}
+
+impl<P: MallocSizeOf> MallocSizeOf for MeteredPool<P> {
+	fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+		self.inner.size_of(ops)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::{pallet_template, Runtime};
+	use sp_runtime::transaction_validity::InvalidTransaction;
+
+	#[test]
+	fn rejections_are_labelled_by_reason() {
+		let reason = |e| rejection_reason(&PoolError::InvalidTransaction(e));
+		assert_eq!(reason(InvalidTransaction::Payment), "Payment");
+		assert_eq!(reason(InvalidTransaction::Stale), "Stale");
+		assert_eq!(reason(InvalidTransaction::Custom(3)), "Custom(3)");
+		assert_eq!(rejection_reason(&PoolError::TemporarilyBanned), "TemporarilyBanned");
+		assert_eq!(rejection_reason(&PoolError::AlreadyImported(Box::new(()))), "AlreadyImported");
+	}
+
+	#[test]
+	fn template_events_are_labelled_by_name() {
+		let event = pallet_template::Event::<Runtime>::SomethingStored(
+			1,
+			sp_keyring::Sr25519Keyring::Alice.to_account_id(),
+		);
+		assert_eq!(variant_name(&event), "SomethingStored");
+	}
+}
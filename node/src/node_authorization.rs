@@ -0,0 +1,147 @@
+//! Restricting the peers of the node to the nodes authorized by `pallet_node_authorization`.
+//!
+//! At startup and at every new best block, the well-known nodes and the additional connections of
+//! this node are read from the state and become the only peers the network accepts. A node that
+//! is well known may connect to the other well-known nodes and to its additional connections;
+//! other nodes only to their additional connections.
+//!
+//! The pallet's offchain worker would do the same on every node running offchain workers, so the
+//! runtime does not run it, and the node only restricts its peers when started with
+//! `--node-authorization`. Its peer id must then be authorized, so it must be started with the
+//! `--node-key` that id is derived from; the dev and local chain specs authorize the keys of
+//! [`crate::chain_spec::peer_id_from_node_key`].
+
+use codec::Decode;
+use frame_support::storage::{StorageMap, StorageValue};
+use futures::StreamExt;
+use node_template_runtime::{opaque::Block, pallet_node_authorization, Hash, Runtime};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_network::{NetworkService, PeerId};
+use sc_network_common::service::{NetworkPeers, NetworkStateInfo};
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::StorageKey, OpaquePeerId};
+use sp_runtime::generic::BlockId;
+use std::{
+	collections::{BTreeSet, HashSet},
+	sync::Arc,
+};
+
+/// Command line options of node authorization.
+#[derive(Debug, Clone, clap::Args)]
+pub struct NodeAuthorizationParams {
+	/// Only accept the peers authorized by `pallet-node-authorization` at the best block.
+	///
+	/// The node must be started with a `--node-key` whose peer id is authorized. Without this
+	/// flag, the node accepts any peer.
+	#[clap(long)]
+	pub node_authorization: bool,
+}
+
+/// The peers `local` may connect to, given the well-known nodes of the chain and the additional
+/// connections of `local`.
+pub fn authorized_peers(
+	local: &PeerId,
+	well_known: BTreeSet<OpaquePeerId>,
+	additional: BTreeSet<OpaquePeerId>,
+) -> HashSet<PeerId> {
+	let local_opaque = OpaquePeerId(local.to_bytes());
+	let mut nodes = additional;
+	if well_known.contains(&local_opaque) {
+		nodes.extend(well_known);
+	}
+	nodes.remove(&local_opaque);
+
+	nodes
+		.into_iter()
+		.filter_map(|node| match PeerId::from_bytes(&node.0) {
+			Ok(peer) => Some(peer),
+			Err(e) => {
+				log::warn!(target: "node-authorization", "Invalid authorized peer id: {}", e);
+				None
+			},
+		})
+		.collect()
+}
+
+/// The peers `local` may connect to, in the state of block `hash`.
+fn peers_at<C, B>(client: &C, hash: Hash, local: &PeerId) -> Result<HashSet<PeerId>, String>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let read = |key: Vec<u8>| -> Result<BTreeSet<OpaquePeerId>, String> {
+		match client
+			.storage(&BlockId::Hash(hash), &StorageKey(key))
+			.map_err(|e| format!("Failed to read the authorized nodes at {:?}: {}", hash, e))?
+		{
+			Some(data) => BTreeSet::decode(&mut &data.0[..])
+				.map_err(|e| format!("Failed to decode the authorized nodes at {:?}: {}", hash, e)),
+			None => Ok(BTreeSet::new()),
+		}
+	};
+
+	let well_known =
+		read(pallet_node_authorization::WellKnownNodes::<Runtime>::hashed_key().to_vec())?;
+	let additional =
+		read(pallet_node_authorization::AdditionalConnections::<Runtime>::hashed_key_for(
+			OpaquePeerId(local.to_bytes()),
+		))?;
+	Ok(authorized_peers(local, well_known, additional))
+}
+
+/// Keep the peers of `network` to the nodes authorized at the best block of `client`, until the
+/// client shuts down.
+pub async fn run<C, B>(client: Arc<C>, network: Arc<NetworkService<Block, Hash>>)
+where
+	C: StorageProvider<Block, B> + BlockchainEvents<Block> + HeaderBackend<Block>,
+	B: Backend<Block>,
+{
+	let local = network.local_peer_id();
+	let mut current = None;
+	let mut update = |hash: Hash| match peers_at(&*client, hash, &local) {
+		Ok(peers) if current.as_ref() != Some(&peers) => {
+			log::info!(
+				target: "node-authorization",
+				"Accepting {} authorized peer(s)",
+				peers.len()
+			);
+			network.set_authorized_peers(peers.clone());
+			network.set_authorized_only(true);
+			current = Some(peers);
+		},
+		Ok(_) => {},
+		Err(e) => log::warn!(target: "node-authorization", "{}", e),
+	};
+
+	update(client.info().best_hash);
+	let mut imports = client.import_notification_stream();
+	while let Some(import) = imports.next().await {
+		if import.is_new_best {
+			update(import.hash);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn opaque(peer: &PeerId) -> OpaquePeerId {
+		OpaquePeerId(peer.to_bytes())
+	}
+
+	#[test]
+	fn well_known_nodes_reach_each_other_and_their_connections() {
+		let (local, other, extra, stranger) =
+			(PeerId::random(), PeerId::random(), PeerId::random(), PeerId::random());
+		let well_known: BTreeSet<_> = [opaque(&local), opaque(&other)].into();
+		let additional: BTreeSet<_> = [opaque(&extra)].into();
+
+		assert_eq!(
+			authorized_peers(&local, well_known.clone(), additional.clone()),
+			[other, extra].into()
+		);
+		// Other nodes only reach their additional connections.
+		assert_eq!(authorized_peers(&stranger, well_known, additional), [extra].into());
+	}
+}
@@ -0,0 +1,183 @@
+//! `system_dryRunDecoded`: apply an extrinsic on top of a block without committing it, and
+//! report its effects decoded.
+
+use crate::decode;
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::{Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use node_template_runtime::{
+	apis::{DryRunApi as DryRunRuntimeApi, DryRunEffects},
+	opaque::{Block, UncheckedExtrinsic},
+	Hash, Header, Weight,
+};
+use sc_rpc_api::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_api::{
+	ApiError, CallApiAt, CallApiAtParams, Metadata, OverlayedChanges, ProvideRuntimeApi,
+	StorageTransactionCache,
+};
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, ExecutionContext};
+use sp_runtime::{generic::BlockId, traits::Header as _};
+use std::{cell::RefCell, fmt::Display, sync::Arc};
+
+/// The extrinsic could not be decoded.
+const DECODE_ERROR: i32 = 1;
+/// The block was not found, or calling the runtime failed.
+const RUNTIME_ERROR: i32 = 2;
+
+/// How applying the extrinsic went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "outcome", content = "error")]
+pub enum DryRunOutcome {
+	/// The call succeeded.
+	Success,
+	/// The extrinsic was included, but its call failed with this error.
+	Failed(String),
+	/// The extrinsic was invalid, and would not be included in a block.
+	Invalid(String),
+}
+
+/// The effects of an extrinsic, as returned by `system_dryRunDecoded`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunReport {
+	/// How applying the extrinsic went.
+	pub result: DryRunOutcome,
+	/// The events deposited while applying the extrinsic, as `{"pallet", "event", "fields"}`.
+	pub events: Vec<Value>,
+	/// The weight the extrinsic used, after refunds.
+	pub actual_weight: Weight,
+	/// The fee the extrinsic paid, tip included.
+	pub fee: Value,
+	/// The storage keys the extrinsic wrote to or removed. The writes of the block initialization
+	/// the extrinsic is applied after are left out, unless the extrinsic wrote the same keys.
+	pub storage_keys: Vec<Bytes>,
+}
+
+/// Dry-run RPC methods.
+#[rpc(server)]
+pub trait DryRunApi {
+	/// Apply a signed or unsigned SCALE-encoded extrinsic in a new block on top of `at`, or of
+	/// the best block, and report its effects. Nothing is committed.
+	#[method(name = "system_dryRunDecoded")]
+	fn dry_run_decoded(&self, extrinsic: Bytes, at: Option<Hash>) -> RpcResult<DryRunReport>;
+}
+
+/// Implements [`DryRunApiServer`] over a client.
+pub struct DryRun<C> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C> DryRun<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, deny_unsafe }
+	}
+}
+
+impl<C> DryRun<C>
+where
+	C: CallApiAt<Block>,
+{
+	/// Call `function` of the runtime at `at` with `arguments`, keeping its changes in `changes`
+	/// on top of the state of `at`.
+	fn call<R: Encode + Decode + PartialEq>(
+		&self,
+		at: &BlockId<Block>,
+		changes: &RefCell<OverlayedChanges>,
+		cache: &RefCell<StorageTransactionCache<Block, C::StateBackend>>,
+		function: &'static str,
+		arguments: Vec<u8>,
+	) -> RpcResult<R> {
+		let encoded = self
+			.client
+			.call_api_at(CallApiAtParams {
+				at,
+				function,
+				native_call: None::<fn() -> Result<R, ApiError>>,
+				arguments,
+				overlayed_changes: changes,
+				storage_transaction_cache: cache,
+				context: ExecutionContext::OffchainCall(None),
+				recorder: &None,
+			})
+			.map_err(|e| error(RUNTIME_ERROR, e))?
+			.into_encoded();
+		R::decode(&mut &encoded[..]).map_err(|e| error(RUNTIME_ERROR, e))
+	}
+}
+
+impl<C> DryRunApiServer for DryRun<C>
+where
+	C: ProvideRuntimeApi<Block> + CallApiAt<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DryRunRuntimeApi<Block> + Metadata<Block>,
+{
+	fn dry_run_decoded(&self, extrinsic: Bytes, at: Option<Hash>) -> RpcResult<DryRunReport> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let extrinsic = UncheckedExtrinsic::decode(&mut &extrinsic[..])
+			.map_err(|e| error(DECODE_ERROR, format!("Failed to decode the extrinsic: {}", e)))?;
+		let parent = at.unwrap_or_else(|| self.client.info().best_hash);
+		let at = BlockId::Hash(parent);
+		let parent_header = self
+			.client
+			.header(at)
+			.map_err(|e| error(RUNTIME_ERROR, e))?
+			.ok_or_else(|| error(RUNTIME_ERROR, format!("Block {:?} not found", parent)))?;
+		let metadata = self
+			.client
+			.runtime_api()
+			.metadata(&at)
+			.map_err(|e| error(RUNTIME_ERROR, e))
+			.and_then(|bytes| decode::metadata(&bytes).map_err(|e| error(RUNTIME_ERROR, e)))?;
+
+		let header = Header::new(
+			parent_header.number + 1,
+			Default::default(),
+			Default::default(),
+			parent,
+			Default::default(),
+		);
+		// The block is initialized once, and the extrinsic applied after it in the same changes,
+		// which are dropped at the end. The changes record the extrinsic index of their writes
+		// from then on only, which tells the writes of the extrinsic apart.
+		let changes = RefCell::new(OverlayedChanges::default());
+		let cache = RefCell::new(StorageTransactionCache::default());
+		self.call::<()>(&at, &changes, &cache, "Core_initialize_block", header.encode())?;
+		changes.borrow_mut().set_collect_extrinsics(true);
+		let DryRunEffects { result, events, actual_weight, fee } =
+			self.call(&at, &changes, &cache, "DryRunApi_dry_run", extrinsic.encode())?;
+		let storage_keys = changes
+			.borrow()
+			.changes()
+			.filter(|(_, value)| !value.extrinsics().is_empty())
+			.map(|(key, _)| key.clone().into())
+			.collect();
+
+		Ok(DryRunReport {
+			result: match result {
+				Ok(Ok(())) => DryRunOutcome::Success,
+				Ok(Err(e)) => DryRunOutcome::Failed(decode::dispatch_error(&metadata, &e)),
+				Err(e) => DryRunOutcome::Invalid(format!("{:?}", e)),
+			},
+			events: events
+				.iter()
+				.map(|event| decode::event(&metadata, &event.encode()))
+				.collect::<Result<_, _>>()
+				.map_err(|e| error(RUNTIME_ERROR, e))?,
+			actual_weight,
+			fee: decode::balance(fee),
+			storage_keys,
+		})
+	}
+}
+
+fn error(code: i32, message: impl Display) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(code, message.to_string(), None::<()>)).into()
+}
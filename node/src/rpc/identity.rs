@@ -0,0 +1,123 @@
+//! `identity_identityOf`: the human-readable name of an account, for tooling that would
+//! otherwise only show SS58 addresses.
+
+use crate::decode;
+use jsonrpsee::{
+	core::{Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use node_template_runtime::{
+	apis::IdentityApi as IdentityRuntimeApi, opaque::Block, pallet_nicknames, AccountId, Balance,
+	Hash,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::BlockId;
+use std::sync::Arc;
+
+/// The block was not found, or calling the runtime failed.
+const RUNTIME_ERROR: i32 = 1;
+
+/// See [`pallet_nicknames::Judgement`].
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Judgement {
+	Unknown,
+	Reasonable,
+	KnownGood,
+	Erroneous,
+}
+
+impl From<pallet_nicknames::Judgement> for Judgement {
+	fn from(judgement: pallet_nicknames::Judgement) -> Self {
+		use pallet_nicknames::Judgement::*;
+		match judgement {
+			Unknown => Self::Unknown,
+			Reasonable => Self::Reasonable,
+			KnownGood => Self::KnownGood,
+			Erroneous => Self::Erroneous,
+		}
+	}
+}
+
+/// The name of an account, as returned by `identity_identityOf`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityInfo {
+	/// The name, with invalid UTF-8 replaced.
+	pub name: String,
+	/// How root judged the name.
+	pub judgement: Judgement,
+	/// The deposit reserved for the name.
+	pub deposit: Value,
+}
+
+impl IdentityInfo {
+	fn from_runtime(nickname: pallet_nicknames::Nickname<Vec<u8>, Balance>) -> Self {
+		Self {
+			name: String::from_utf8_lossy(&nickname.name).into_owned(),
+			judgement: nickname.judgement.into(),
+			deposit: decode::balance(nickname.deposit),
+		}
+	}
+}
+
+/// Identity RPC methods.
+#[rpc(server)]
+pub trait IdentityApi {
+	/// The name of `account` in the state of block `at`, or of the best block, if it has one.
+	#[method(name = "identity_identityOf")]
+	fn identity_of(&self, account: AccountId, at: Option<Hash>) -> RpcResult<Option<IdentityInfo>>;
+}
+
+/// Implements [`IdentityApiServer`] over a client.
+pub struct Identity<C> {
+	client: Arc<C>,
+}
+
+impl<C> Identity<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> IdentityApiServer for Identity<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: IdentityRuntimeApi<Block>,
+{
+	fn identity_of(&self, account: AccountId, at: Option<Hash>) -> RpcResult<Option<IdentityInfo>> {
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let nickname = self.client.runtime_api().identity_of(&at, account).map_err(|e| {
+			JsonRpseeError::from(CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				format!("Failed to read the identity: {}", e),
+				None::<()>,
+			)))
+		})?;
+		Ok(nickname.map(IdentityInfo::from_runtime))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn identities_are_sent_with_readable_names() {
+		let nickname = pallet_nicknames::Nickname {
+			name: b"Alice".to_vec(),
+			deposit: 50_000,
+			judgement: pallet_nicknames::Judgement::KnownGood,
+		};
+		assert_eq!(
+			serde_json::to_value(IdentityInfo::from_runtime(nickname)).unwrap(),
+			serde_json::json!({ "name": "Alice", "judgement": "knownGood", "deposit": 50_000 })
+		);
+	}
+}
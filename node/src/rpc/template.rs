@@ -0,0 +1,249 @@
+//! `template_subscribeEvents`: a subscription to the events of the template pallet, decoded.
+//!
+//! Events are sent once their block becomes the best block, again when it is finalized, and
+//! once more, as retracted, if a reorg takes the block out of the best chain.
+
+use crate::events::block_events;
+use futures::{stream, FutureExt, StreamExt};
+use jsonrpsee::{proc_macros::rpc, types::SubscriptionResult, SubscriptionSink};
+use node_template_runtime::{
+	opaque::Block, pallet_template, AccountId, BlockNumber, Event, Hash, Runtime,
+};
+use sc_client_api::{
+	Backend, BlockImportNotification, BlockchainEvents, FinalityNotification, StorageProvider,
+};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use std::{marker::PhantomData, sync::Arc};
+
+/// A template pallet event, with its fields named after the event's documentation.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "name")]
+pub enum TemplateEvent {
+	/// See [`pallet_template::Event::SomethingStored`].
+	SomethingStored { something: u32, who: AccountId },
+	/// See [`pallet_template::Event::RoundStarted`].
+	#[serde(rename_all = "camelCase")]
+	RoundStarted { round: u32, commit_end: BlockNumber, reveal_end: BlockNumber },
+	/// See [`pallet_template::Event::Committed`].
+	Committed { round: u32, who: AccountId },
+	/// See [`pallet_template::Event::Revealed`].
+	Revealed { round: u32, who: AccountId },
+	/// See [`pallet_template::Event::WinnerDrawn`].
+	WinnerDrawn { round: u32, winner: AccountId },
+	/// See [`pallet_template::Event::RoundExpired`].
+	RoundExpired { round: u32 },
+}
+
+impl TemplateEvent {
+	fn from_runtime(event: pallet_template::Event<Runtime>) -> Option<Self> {
+		use pallet_template::Event::*;
+		Some(match event {
+			SomethingStored(something, who) => Self::SomethingStored { something, who },
+			RoundStarted(round, commit_end, reveal_end) =>
+				Self::RoundStarted { round, commit_end, reveal_end },
+			Committed(round, who) => Self::Committed { round, who },
+			Revealed(round, who) => Self::Revealed { round, who },
+			WinnerDrawn(round, winner) => Self::WinnerDrawn { round, winner },
+			RoundExpired(round) => Self::RoundExpired { round },
+			_ => return None,
+		})
+	}
+}
+
+/// Where the block of an event stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventStatus {
+	/// The block became the best block.
+	InBlock,
+	/// The block was finalized.
+	Finalized,
+	/// The block left the best chain in a reorg.
+	Retracted,
+}
+
+/// An item of the `template_subscribeEvents` subscription.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventNotification {
+	/// Hash of the block that deposited the event.
+	pub block_hash: Hash,
+	/// Index of the extrinsic that deposited the event, or `None` for block initialization
+	/// and finalization.
+	pub extrinsic_index: Option<u32>,
+	/// Status of the block.
+	pub status: EventStatus,
+	/// The event.
+	pub event: TemplateEvent,
+}
+
+/// Template pallet RPC methods.
+#[rpc(server)]
+pub trait TemplateApi {
+	/// Subscribe to the decoded events of the template pallet.
+	#[subscription(
+		name = "template_subscribeEvents" => "template_events",
+		unsubscribe = "template_unsubscribeEvents",
+		item = EventNotification,
+	)]
+	fn subscribe_events(&self);
+}
+
+/// Implements [`TemplateApiServer`] over a client.
+pub struct Template<C, B> {
+	client: Arc<C>,
+	executor: SubscriptionTaskExecutor,
+	_backend: PhantomData<B>,
+}
+
+impl<C, B> Template<C, B> {
+	/// Create a new instance, spawning subscriptions on `executor`.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { client, executor, _backend: PhantomData }
+	}
+}
+
+/// A notification of the client that changes the status of blocks.
+enum ChainChange {
+	Import(BlockImportNotification<Block>),
+	Finality(FinalityNotification<Block>),
+}
+
+impl<C, B> TemplateApiServer for Template<C, B>
+where
+	C: StorageProvider<Block, B> + BlockchainEvents<Block> + Send + Sync + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	fn subscribe_events(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+		let client = self.client.clone();
+		let imports = client.import_notification_stream().map(ChainChange::Import);
+		let finality = client.finality_notification_stream().map(ChainChange::Finality);
+		let notifications = stream::select(imports, finality)
+			.flat_map(move |change| stream::iter(notifications(&*client, change)));
+
+		let fut = async move {
+			sink.pipe_from_stream(notifications).await;
+		};
+		self.executor.spawn("template-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
+}
+
+/// The notifications for the template events of the blocks whose status `change` changes.
+fn notifications<C, B>(client: &C, change: ChainChange) -> Vec<EventNotification>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let blocks: Vec<(Hash, EventStatus)> = match change {
+		ChainChange::Import(import) if import.is_new_best => {
+			// The tree route goes from the previous best block to the parent of the new one.
+			let mut blocks = Vec::new();
+			if let Some(route) = &import.tree_route {
+				blocks.extend(route.retracted().iter().map(|b| (b.hash, EventStatus::Retracted)));
+				blocks.extend(route.enacted().iter().map(|b| (b.hash, EventStatus::InBlock)));
+			}
+			blocks.push((import.hash, EventStatus::InBlock));
+			blocks
+		},
+		ChainChange::Import(_) => Vec::new(),
+		ChainChange::Finality(finality) => finality
+			.tree_route
+			.iter()
+			.chain(std::iter::once(&finality.hash))
+			.map(|hash| (*hash, EventStatus::Finalized))
+			.collect(),
+	};
+
+	blocks
+		.into_iter()
+		.flat_map(|(hash, status)| match block_events(client, hash) {
+			Ok(records) => template_events(hash, status, records),
+			Err(e) => {
+				log::warn!(target: "rpc", "{}", e);
+				Vec::new()
+			},
+		})
+		.collect()
+}
+
+/// The notifications for the template events among `records`, deposited by block `block_hash`.
+fn template_events(
+	block_hash: Hash,
+	status: EventStatus,
+	records: Vec<frame_system::EventRecord<Event, Hash>>,
+) -> Vec<EventNotification> {
+	records
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::TemplateModule(event) => Some(EventNotification {
+				block_hash,
+				extrinsic_index: match record.phase {
+					frame_system::Phase::ApplyExtrinsic(index) => Some(index),
+					_ => None,
+				},
+				status,
+				event: TemplateEvent::from_runtime(event)?,
+			}),
+			_ => None,
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_system::{EventRecord, Phase};
+	use sp_keyring::Sr25519Keyring;
+
+	#[test]
+	fn only_template_events_are_sent() {
+		let alice = Sr25519Keyring::Alice.to_account_id();
+		let record = |phase, event: Event| EventRecord { phase, event, topics: vec![] };
+		let records = vec![
+			record(
+				Phase::Initialization,
+				pallet_template::Event::<Runtime>::RoundExpired(3).into(),
+			),
+			record(
+				Phase::ApplyExtrinsic(1),
+				frame_system::Event::<Runtime>::NewAccount { account: alice.clone() }.into(),
+			),
+			record(
+				Phase::ApplyExtrinsic(1),
+				pallet_template::Event::<Runtime>::SomethingStored(42, alice.clone()).into(),
+			),
+		];
+
+		let hash = Hash::repeat_byte(1);
+		let notifications = template_events(hash, EventStatus::Finalized, records);
+		assert_eq!(
+			notifications,
+			vec![
+				EventNotification {
+					block_hash: hash,
+					extrinsic_index: None,
+					status: EventStatus::Finalized,
+					event: TemplateEvent::RoundExpired { round: 3 },
+				},
+				EventNotification {
+					block_hash: hash,
+					extrinsic_index: Some(1),
+					status: EventStatus::Finalized,
+					event: TemplateEvent::SomethingStored { something: 42, who: alice.clone() },
+				},
+			]
+		);
+		assert_eq!(
+			serde_json::to_value(&notifications[1]).unwrap(),
+			serde_json::json!({
+				"blockHash": format!("{:?}", hash),
+				"extrinsicIndex": 1,
+				"status": "finalized",
+				"event": { "name": "somethingStored", "something": 42, "who": alice.to_string() },
+			})
+		);
+	}
+}
@@ -1,15 +1,32 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use crate::{
+	health::{HealthConfig, Status},
+	metrics::{MeteredPool, Metrics},
+};
+use futures::StreamExt;
 use node_template_runtime::{self, opaque::Block, RuntimeApi};
-use sc_client_api::{BlockBackend, ExecutorProvider};
+use sc_client_api::{BlockBackend, BlockchainEvents, ExecutorProvider};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
 pub use sc_executor::NativeElseWasmExecutor;
 use sc_finality_grandpa::SharedVoterState;
 use sc_keystore::LocalKeystore;
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_service::{error::Error as ServiceError, ChainType, Configuration, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
-use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
-use std::{sync::Arc, time::Duration};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::SyncOracle;
+use sp_consensus_aura::{sr25519::AuthorityPair as AuraPair, AuraApi};
+use sp_core::crypto::{key_types::AURA, ByteArray};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
 
 // Our native executor instance.
 pub struct ExecutorDispatch;
@@ -31,11 +48,26 @@ impl sc_executor::NativeExecutionDispatch for ExecutorDispatch {
 	}
 }
 
-pub(crate) type FullClient =
+pub type FullClient =
 	sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
+/// The transaction pool of a full node, counting the transactions it refuses.
+pub type FullPool = MeteredPool<sc_transaction_pool::FullPool<Block, FullClient>>;
+
+/// A full node started by [`new_full`].
+pub struct FullNode {
+	/// The tasks of the node. Dropping it stops the node.
+	pub task_manager: TaskManager,
+	/// The client of the node.
+	pub client: Arc<FullClient>,
+	/// The transaction pool of the node.
+	pub transaction_pool: Arc<FullPool>,
+	/// The keystore of the node.
+	pub keystore: SyncCryptoStorePtr,
+}
+
 pub fn new_partial(
 	config: &Configuration,
 ) -> Result<
@@ -156,8 +188,31 @@ fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
 	Err("Remote Keystore not supported.")
 }
 
-/// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+/// Whether `keystore` holds the Aura key of one of the authorities at block `at`. Aura keys are
+/// the only authoring keys this node needs.
+fn holds_aura_key(
+	client: &FullClient,
+	keystore: &dyn SyncCryptoStore,
+	at: <Block as BlockT>::Hash,
+) -> bool {
+	client
+		.runtime_api()
+		.authorities(&BlockId::Hash(at))
+		.map(|authorities| {
+			authorities.iter().any(|authority| {
+				SyncCryptoStore::has_keys(keystore, &[(authority.to_raw_vec(), AURA)])
+			})
+		})
+		.unwrap_or(false)
+}
+
+/// Builds a new service for a full client, serving health checks if `health` is given and only
+/// accepting the peers authorized on chain if `node_authorization` is set.
+pub fn new_full(
+	mut config: Configuration,
+	health: Option<HealthConfig>,
+	node_authorization: bool,
+) -> Result<FullNode, ServiceError> {
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -169,6 +224,11 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		other: (block_import, grandpa_link, mut telemetry),
 	} = new_partial(&config)?;
 
+	let transaction_pool = Arc::new(
+		MeteredPool::new(transaction_pool, config.prometheus_registry())
+			.map_err(|e| ServiceError::Other(format!("Failed to register metrics: {}", e)))?,
+	);
+
 	if let Some(url) = &config.keystore_remote {
 		match remote_keystore(url) {
 			Ok(k) => keystore_container.set_remote_keystore(k),
@@ -179,6 +239,8 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 				))),
 		};
 	}
+	let (node_client, node_pool, keystore) =
+		(client.clone(), transaction_pool.clone(), keystore_container.sync_keystore());
 	let grandpa_protocol_name = sc_finality_grandpa::protocol_standard_name(
 		&client.block_hash(0).ok().flatten().expect("Genesis block exists; qed"),
 		&config.chain_spec,
@@ -205,6 +267,14 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			warp_sync: Some(warp_sync),
 		})?;
 
+	if node_authorization {
+		task_manager.spawn_handle().spawn(
+			"node-authorization",
+			None,
+			crate::node_authorization::run::<_, FullBackend>(client.clone(), network.clone()),
+		);
+	}
+
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config,
@@ -221,13 +291,67 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	if let Some(registry) = prometheus_registry.as_ref() {
+		let metrics = Metrics::register(registry)
+			.map_err(|e| ServiceError::Other(format!("Failed to register metrics: {}", e)))?;
+		task_manager.spawn_handle().spawn(
+			"template-metrics",
+			None,
+			crate::metrics::run(client.clone(), metrics),
+		);
+	}
+
+	if let Some(health) = health {
+		// Looked up once per new best block rather than on every request, since the endpoint
+		// does not authenticate its callers.
+		let is_authoring = Arc::new(AtomicBool::new(false));
+		if role.is_authority() {
+			let client = client.clone();
+			let keystore = keystore_container.sync_keystore();
+			let is_authoring = is_authoring.clone();
+			task_manager.spawn_handle().spawn("health-authoring", None, async move {
+				let update = |hash| {
+					is_authoring.store(holds_aura_key(&client, &*keystore, hash), Ordering::Relaxed)
+				};
+				update(client.info().best_hash);
+				let mut imports = client.import_notification_stream();
+				while let Some(import) = imports.next().await {
+					if import.is_new_best {
+						update(import.hash);
+					}
+				}
+			});
+		}
+
+		let client = client.clone();
+		let network = network.clone();
+		let should_have_peers = config.chain_spec.chain_type() != ChainType::Development;
+		let status = Arc::new(move || {
+			let info = client.info();
+			Status {
+				peers: network.num_connected(),
+				is_syncing: network.is_major_syncing(),
+				should_have_peers,
+				best: info.best_number,
+				finalized: info.finalized_number,
+				is_authoring: is_authoring.load(Ordering::Relaxed),
+			}
+		});
+		let server = crate::health::serve(health, status).map_err(ServiceError::Other)?;
+		task_manager.spawn_handle().spawn("health", None, server);
+	}
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
+		Box::new(move |deny_unsafe, executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				executor,
+			};
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
 	};
@@ -340,5 +464,5 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	}
 
 	network_starter.start_network();
-	Ok(task_manager)
+	Ok(FullNode { task_manager, client: node_client, transaction_pool: node_pool, keystore })
 }
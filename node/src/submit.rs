@@ -0,0 +1,304 @@
+//! Submitting transactions signed with keys from the node's keystore and following them into
+//! blocks.
+//!
+//! [`TransactionSubmitter`] picks the nonce itself, counting the transactions of the signer that
+//! are already waiting in the pool, so that several calls can be submitted back to back.
+
+use crate::{
+	events::block_events,
+	extrinsic::{default_mortality, signed_payload, ChainContext},
+	service::FullClient,
+};
+use codec::Encode;
+use frame_system::{EventRecord, Phase};
+use frame_system_rpc_runtime_api::AccountNonceApi;
+use futures::{lock::Mutex, stream::BoxStream, StreamExt};
+use node_template_runtime::{self as runtime, opaque::Block, AccountId, Event, Hash, Index};
+use sc_client_api::BlockBackend;
+use sc_transaction_pool_api::{
+	InPoolTransaction, TransactionPool, TransactionSource, TransactionStatus,
+};
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	crypto::{key_types, CryptoTypePublicPair, KeyTypeId},
+	sr25519,
+};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{
+	generic::{BlockId, Era},
+	traits::{BlakeTwo256, Hash as _},
+	transaction_validity::TransactionTag,
+	DispatchResult, OpaqueExtrinsic,
+};
+use std::sync::Arc;
+
+/// Key type under which the keystore holds the accounts that [`TransactionSubmitter`] signs for.
+///
+/// Keys are added with `key insert --key-type acco --scheme sr25519`.
+pub const KEY_TYPE: KeyTypeId = key_types::ACCOUNT;
+
+/// Signs calls with keys from a keystore and submits them to the transaction pool.
+pub struct TransactionSubmitter<P> {
+	client: Arc<FullClient>,
+	pool: Arc<P>,
+	keystore: SyncCryptoStorePtr,
+	/// Held from picking a nonce until the transaction is in the pool, so that concurrent
+	/// submissions do not pick the same one.
+	submitting: Mutex<()>,
+}
+
+impl<P> TransactionSubmitter<P>
+where
+	P: TransactionPool<Block = Block, Hash = Hash> + 'static,
+{
+	/// A submitter for the chain of `client`, signing with keys from `keystore` and submitting to
+	/// `pool`.
+	pub fn new(client: Arc<FullClient>, pool: Arc<P>, keystore: SyncCryptoStorePtr) -> Self {
+		Self { client, pool, keystore, submitting: Mutex::new(()) }
+	}
+
+	/// The nonce for the next transaction of `who`: its nonce at the best block, advanced past
+	/// its transactions that are ready in the pool.
+	pub fn next_nonce(&self, who: &AccountId) -> Result<Index, String> {
+		let best = self.client.info().best_hash;
+		let nonce = self
+			.client
+			.runtime_api()
+			.account_nonce(&BlockId::Hash(best), who.clone())
+			.map_err(|e| format!("Failed to read the nonce of {}: {}", who, e))?;
+
+		Ok(pending_nonce(who, nonce, self.pool.ready().map(|tx| tx.provides().to_vec())))
+	}
+
+	/// Sign `call` as `signer`, whose key must be in the keystore, and submit it to the pool.
+	pub async fn submit(
+		&self,
+		signer: &sr25519::Public,
+		call: impl Into<runtime::Call>,
+	) -> Result<Watched, String> {
+		let _submitting = self.submitting.lock().await;
+
+		let who = AccountId::from(*signer);
+		let info = self.client.info();
+		let at = BlockId::Hash(info.best_hash);
+		let genesis_hash = self
+			.client
+			.hash(0)
+			.map_err(|e| format!("Failed to read the genesis hash: {}", e))?
+			.ok_or("Genesis block not found")?;
+		let version = self
+			.client
+			.runtime_api()
+			.version(&at)
+			.map_err(|e| format!("Failed to read the runtime version: {}", e))?;
+		let nonce = self.next_nonce(&who)?;
+
+		let payload = signed_payload(
+			call.into(),
+			nonce,
+			0,
+			Era::mortal(default_mortality(), u64::from(info.best_number)),
+			info.best_hash,
+			&ChainContext::new(genesis_hash, &version),
+		);
+		let signature = payload
+			.using_encoded(|e| {
+				SyncCryptoStore::sign_with(
+					&*self.keystore,
+					KEY_TYPE,
+					&CryptoTypePublicPair::from(*signer),
+					e,
+				)
+			})
+			.map_err(|e| format!("Failed to sign as {}: {}", who, e))?
+			.ok_or_else(|| format!("No key for {} in the keystore", who))?;
+		let signature = sr25519::Signature::try_from(&signature[..])
+			.map_err(|_| "The keystore returned a malformed signature".to_string())?;
+		let (call, extra, _) = payload.deconstruct();
+		let xt = runtime::UncheckedExtrinsic::new_signed(call, who.into(), signature.into(), extra);
+
+		let xt = OpaqueExtrinsic::from(xt);
+		let hash = self.pool.hash_of(&xt);
+		let status = self
+			.pool
+			.submit_and_watch(&at, TransactionSource::Local, xt)
+			.await
+			.map_err(|e| format!("Transaction rejected by the pool: {}", e))?;
+
+		Ok(Watched { client: self.client.clone(), hash, status })
+	}
+}
+
+/// A transaction in the pool, whose progress can be awaited.
+pub struct Watched {
+	client: Arc<FullClient>,
+	hash: Hash,
+	status: BoxStream<'static, TransactionStatus<Hash, Hash>>,
+}
+
+impl Watched {
+	/// Hash of the transaction.
+	pub fn hash(&self) -> Hash {
+		self.hash
+	}
+
+	/// Wait until the transaction is included in a block.
+	pub async fn in_block(self) -> Result<Inclusion, String> {
+		self.wait(false).await
+	}
+
+	/// Wait until the transaction is included in a finalized block.
+	pub async fn finalized(self) -> Result<Inclusion, String> {
+		self.wait(true).await
+	}
+
+	async fn wait(mut self, finalized: bool) -> Result<Inclusion, String> {
+		while let Some(status) = self.status.next().await {
+			match status {
+				TransactionStatus::InBlock(block) if !finalized => return self.inclusion(block),
+				TransactionStatus::Finalized(block) => return self.inclusion(block),
+				TransactionStatus::FinalityTimeout(block) =>
+					return Err(format!(
+						"Transaction {:?} in block {:?} was not finalized in time",
+						self.hash, block
+					)),
+				TransactionStatus::Usurped(by) =>
+					return Err(format!("Transaction {:?} was replaced by {:?}", self.hash, by)),
+				TransactionStatus::Dropped =>
+					return Err(format!("Transaction {:?} was dropped from the pool", self.hash)),
+				TransactionStatus::Invalid =>
+					return Err(format!("Transaction {:?} became invalid", self.hash)),
+				_ => {},
+			}
+		}
+		Err(format!("Stopped receiving the status of transaction {:?}", self.hash))
+	}
+
+	/// Find the transaction in `block_hash` and the events it emitted.
+	fn inclusion(&self, block_hash: Hash) -> Result<Inclusion, String> {
+		let at = BlockId::Hash(block_hash);
+		let body = self
+			.client
+			.block_body(&at)
+			.map_err(|e| format!("Failed to read block {:?}: {}", block_hash, e))?
+			.ok_or_else(|| format!("Block {:?} has no body", block_hash))?;
+		let index = body
+			.iter()
+			.position(|xt| xt.using_encoded(BlakeTwo256::hash) == self.hash)
+			.ok_or_else(|| format!("Transaction {:?} not in block {:?}", self.hash, block_hash))?
+			as u32;
+
+		let records = block_events(&*self.client, block_hash)?;
+		let (events, result) = outcome(records, index);
+
+		Ok(Inclusion { block_hash, index, events, result })
+	}
+}
+
+/// Where a transaction was included and what it did.
+#[derive(Debug)]
+pub struct Inclusion {
+	/// Hash of the block that includes the transaction.
+	pub block_hash: Hash,
+	/// Position of the transaction in the block.
+	pub index: u32,
+	/// The events the transaction emitted, including the fee payment.
+	pub events: Vec<Event>,
+	/// Whether the call succeeded.
+	pub result: DispatchResult,
+}
+
+/// `nonce` advanced past the transactions of `who` in `ready`, given the tags each provides.
+///
+/// Ready transactions come in dependency order, so one pass finds every pending nonce. The tag
+/// of `CheckNonce` is looked for among all the tags, since other signed extensions may provide
+/// tags as well.
+fn pending_nonce(
+	who: &AccountId,
+	mut nonce: Index,
+	ready: impl Iterator<Item = Vec<TransactionTag>>,
+) -> Index {
+	let mut tag = (who, nonce).encode();
+	for provides in ready {
+		if provides.contains(&tag) {
+			nonce += 1;
+			tag = (who, nonce).encode();
+		}
+	}
+	nonce
+}
+
+/// The events of the extrinsic at `index` among `records`, and the result of its call.
+fn outcome(records: Vec<EventRecord<Event, Hash>>, index: u32) -> (Vec<Event>, DispatchResult) {
+	let mut result = Ok(());
+	let events = records
+		.into_iter()
+		.filter(|record| record.phase == Phase::ApplyExtrinsic(index))
+		.map(|record| {
+			if let Event::System(frame_system::Event::ExtrinsicFailed { dispatch_error, .. }) =
+				&record.event
+			{
+				result = Err(*dispatch_error);
+			}
+			record.event
+		})
+		.collect();
+	(events, result)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::weights::DispatchInfo;
+	use sp_keyring::Sr25519Keyring;
+	use sp_runtime::DispatchError;
+
+	fn record(index: u32, event: impl Into<Event>) -> EventRecord<Event, Hash> {
+		EventRecord { phase: Phase::ApplyExtrinsic(index), event: event.into(), topics: vec![] }
+	}
+
+	#[test]
+	fn pending_nonce_skips_ready_transactions_of_the_account() {
+		let alice = Sr25519Keyring::Alice.to_account_id();
+		let bob = Sr25519Keyring::Bob.to_account_id();
+		// Other signed extensions may provide tags before the one of `CheckNonce`.
+		let tags = |who: &AccountId, nonce: Index| vec![b"other".to_vec(), (who, nonce).encode()];
+		let ready = vec![tags(&alice, 3), tags(&bob, 3), tags(&alice, 4), tags(&alice, 6)];
+
+		assert_eq!(pending_nonce(&alice, 3, ready.clone().into_iter()), 5);
+		assert_eq!(pending_nonce(&bob, 3, ready.clone().into_iter()), 4);
+		assert_eq!(pending_nonce(&bob, 0, ready.into_iter()), 0);
+	}
+
+	#[test]
+	fn outcome_collects_events_of_the_extrinsic() {
+		let info = DispatchInfo::default();
+		let records = vec![
+			record(0, frame_system::Event::ExtrinsicSuccess { dispatch_info: info }),
+			record(
+				1,
+				runtime::pallet_template::Event::SomethingStored(
+					7,
+					Sr25519Keyring::Bob.to_account_id(),
+				),
+			),
+			record(1, frame_system::Event::ExtrinsicSuccess { dispatch_info: info }),
+			record(
+				2,
+				frame_system::Event::ExtrinsicFailed {
+					dispatch_error: DispatchError::BadOrigin,
+					dispatch_info: info,
+				},
+			),
+		];
+
+		let (events, result) = outcome(records.clone(), 1);
+		assert_eq!(events, vec![records[1].event.clone(), records[2].event.clone()]);
+		assert_eq!(result, Ok(()));
+
+		let (events, result) = outcome(records.clone(), 2);
+		assert_eq!(events, vec![records[3].event.clone()]);
+		assert_eq!(result, Err(DispatchError::BadOrigin));
+	}
+}
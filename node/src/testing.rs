@@ -0,0 +1,362 @@
+//! In-process test networks for end-to-end tests.
+//!
+//! [`TestNetwork::start`] runs a few validator nodes in the current process, each with a
+//! temporary database and its own local ports, and returns once they are connected to each
+//! other and have finalized a block. Tests then talk to the nodes over RPC:
+//!
+//! ```ignore
+//! let network = TestNetwork::start(2).await?;
+//! network.wait_for_finalized(3, Duration::from_secs(120)).await?;
+//! let client = network.nodes()[1].rpc().await?;
+//! ```
+//!
+//! Nodes run on the Tokio runtime of the caller, which must be multi-threaded.
+
+use crate::{
+	chain_spec::{
+		authority_keys_from_seed, dev_names, get_account_id_from_seed, peer_id_from_node_key,
+		testnet_genesis, ChainSpec,
+	},
+	service::{self, FullClient, FullNode, FullPool},
+	submit::TransactionSubmitter,
+};
+use clap::Parser;
+use jsonrpsee::{
+	core::{client::ClientT, DeserializeOwned},
+	rpc_params,
+	types::ParamsSer,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use node_template_runtime::{BlockNumber, Hash, Header, WASM_BINARY};
+use sc_cli::{CliConfiguration, RunCmd, RuntimeVersion, SubstrateCli};
+use sc_rpc_api::system::Health;
+use sc_service::{ChainType, TaskManager};
+use sp_core::sr25519;
+use sp_keystore::SyncCryptoStorePtr;
+use std::{
+	net::TcpListener,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use tempfile::TempDir;
+
+/// Keyring names of the validators, in the order they are started.
+pub const VALIDATORS: [&str; 6] = ["Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie"];
+
+/// How long [`TestNetwork::start`] waits for the nodes to connect and finalize a block.
+const START_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often the nodes are polled while waiting for them.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A node of a [`TestNetwork`]. Dropping it stops the node and removes its database.
+pub struct TestNode {
+	name: &'static str,
+	peer_id: String,
+	p2p_port: u16,
+	ws_port: u16,
+	task_manager: TaskManager,
+	client: Arc<FullClient>,
+	transaction_pool: Arc<FullPool>,
+	keystore: SyncCryptoStorePtr,
+	_base_path: TempDir,
+}
+
+impl TestNode {
+	/// Keyring name of the validator that runs the node.
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	/// Address other nodes can connect to, including the peer id.
+	pub fn multiaddr(&self) -> String {
+		format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", self.p2p_port, self.peer_id)
+	}
+
+	/// URL of the WebSocket RPC server.
+	pub fn ws_url(&self) -> String {
+		format!("ws://127.0.0.1:{}", self.ws_port)
+	}
+
+	/// Open an RPC connection to the node.
+	pub async fn rpc(&self) -> Result<WsClient, String> {
+		WsClientBuilder::default()
+			.build(self.ws_url())
+			.await
+			.map_err(|e| format!("Failed to connect to {}: {}", self.name, e))
+	}
+
+	/// The task manager of the node, e.g. to spawn tasks that stop with it.
+	pub fn task_manager(&self) -> &TaskManager {
+		&self.task_manager
+	}
+
+	/// The keystore of the node.
+	pub fn keystore(&self) -> SyncCryptoStorePtr {
+		self.keystore.clone()
+	}
+
+	/// A submitter of transactions to the pool of the node, signed with keys from its keystore.
+	pub fn submitter(&self) -> TransactionSubmitter<FullPool> {
+		TransactionSubmitter::new(
+			self.client.clone(),
+			self.transaction_pool.clone(),
+			self.keystore.clone(),
+		)
+	}
+
+	/// Number of the last block the node finalized.
+	pub async fn finalized_number(&self) -> Result<BlockNumber, String> {
+		let client = self.rpc().await?;
+		let hash: Hash = request(&client, "chain_getFinalizedHead", rpc_params![]).await?;
+		let header: Header = request(&client, "chain_getHeader", rpc_params![hash]).await?;
+		Ok(header.number)
+	}
+
+	/// Number of peers the node is connected to.
+	pub async fn peers(&self) -> Result<usize, String> {
+		let client = self.rpc().await?;
+		let health: Health = request(&client, "system_health", rpc_params![]).await?;
+		Ok(health.peers)
+	}
+}
+
+/// Validator nodes running in the current process.
+pub struct TestNetwork {
+	nodes: Vec<TestNode>,
+}
+
+impl TestNetwork {
+	/// Start `validators` nodes, run by the first validators of [`VALIDATORS`], on a chain
+	/// where they are the only authorities, and wait until they are all connected and have
+	/// finalized a block.
+	pub async fn start(validators: usize) -> Result<Self, String> {
+		if validators == 0 || validators > VALIDATORS.len() {
+			return Err(format!("A test network has 1 to {} validators", VALIDATORS.len()))
+		}
+
+		let cli = TestCli { validators };
+		let mut nodes: Vec<TestNode> = Vec::with_capacity(validators);
+		for (index, name) in VALIDATORS[..validators].iter().enumerate() {
+			let node = start_node(&cli, index, name, &nodes)?;
+			nodes.push(node);
+		}
+
+		let network = Self { nodes };
+		network.wait_for_peers(START_TIMEOUT).await?;
+		network.wait_for_finalized(1, START_TIMEOUT).await?;
+		Ok(network)
+	}
+
+	/// The nodes, in the order of [`VALIDATORS`].
+	pub fn nodes(&self) -> &[TestNode] {
+		&self.nodes
+	}
+
+	/// Wait until every node is connected to all the others.
+	pub async fn wait_for_peers(&self, timeout: Duration) -> Result<(), String> {
+		let expected = self.nodes.len() - 1;
+		self.wait_for(timeout, "all nodes to be connected", |node| async move {
+			Ok(node.peers().await? >= expected)
+		})
+		.await
+	}
+
+	/// Wait until every node has finalized block `number`.
+	pub async fn wait_for_finalized(
+		&self,
+		number: BlockNumber,
+		timeout: Duration,
+	) -> Result<(), String> {
+		self.wait_for(timeout, &format!("block {} to be finalized", number), |node| async move {
+			Ok(node.finalized_number().await? >= number)
+		})
+		.await
+	}
+
+	/// Poll the nodes until `done` holds for each of them.
+	async fn wait_for<'a, F, Fut>(
+		&'a self,
+		timeout: Duration,
+		what: &str,
+		done: F,
+	) -> Result<(), String>
+	where
+		F: Fn(&'a TestNode) -> Fut,
+		Fut: std::future::Future<Output = Result<bool, String>>,
+	{
+		let deadline = Instant::now() + timeout;
+		for node in &self.nodes {
+			while !done(node).await? {
+				if Instant::now() > deadline {
+					return Err(format!("Timed out waiting for {} on {}", what, node.name))
+				}
+				tokio::time::sleep(POLL_INTERVAL).await;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Start the node of validator `name`, connecting to the nodes already `running`.
+fn start_node(
+	cli: &TestCli,
+	index: usize,
+	name: &'static str,
+	running: &[TestNode],
+) -> Result<TestNode, String> {
+	let base_path =
+		TempDir::new().map_err(|e| format!("Failed to create a database directory: {}", e))?;
+	let p2p_port = free_port()?;
+	let ws_port = free_port()?;
+
+	let mut args = vec![
+		"node-template".to_string(),
+		"--chain=test".to_string(),
+		format!("--base-path={}", base_path.path().display()),
+		format!("--{}", name.to_lowercase()),
+		// Fixed node keys give every node a known peer id.
+		format!("--node-key={:064x}", index + 1),
+		format!("--listen-addr=/ip4/127.0.0.1/tcp/{}", p2p_port),
+		format!("--ws-port={}", ws_port),
+		format!("--rpc-port={}", free_port()?),
+		"--no-prometheus".to_string(),
+		"--no-telemetry".to_string(),
+		"--no-mdns".to_string(),
+	];
+	if cli.validators == 1 {
+		// A lone validator has no peers, and would otherwise wait for some before authoring.
+		args.push("--force-authoring".to_string());
+	}
+	args.extend(running.iter().map(|node| format!("--bootnodes={}", node.multiaddr())));
+
+	let cmd = RunCmd::try_parse_from(args).map_err(|e| e.to_string())?;
+	let config = cmd
+		.create_configuration(cli, tokio::runtime::Handle::current())
+		.map_err(|e| format!("Failed to configure {}: {}", name, e))?;
+	let peer_id = config
+		.network
+		.node_key
+		.clone()
+		.into_keypair()
+		.map_err(|e| format!("Failed to load the node key of {}: {}", name, e))?
+		.public()
+		.to_peer_id()
+		.to_string();
+	// The test chain authorizes the nodes of the network, so they run as a permissioned network.
+	let FullNode { task_manager, client, transaction_pool, keystore } =
+		service::new_full(config, None, true)
+			.map_err(|e| format!("Failed to start {}: {}", name, e))?;
+
+	Ok(TestNode {
+		name,
+		peer_id,
+		p2p_port,
+		ws_port,
+		task_manager,
+		client,
+		transaction_pool,
+		keystore,
+		_base_path: base_path,
+	})
+}
+
+/// A local port that is free right now.
+fn free_port() -> Result<u16, String> {
+	TcpListener::bind("127.0.0.1:0")
+		.and_then(|listener| listener.local_addr())
+		.map(|addr| addr.port())
+		.map_err(|e| format!("Failed to find a free port: {}", e))
+}
+
+/// Make the RPC call `method` and decode its result.
+async fn request<R: DeserializeOwned>(
+	client: &WsClient,
+	method: &str,
+	params: Option<ParamsSer<'_>>,
+) -> Result<R, String> {
+	client
+		.request(method, params)
+		.await
+		.map_err(|e| format!("RPC call `{}` failed: {}", method, e))
+}
+
+/// Configures test nodes for a chain whose authorities are the first `validators` of
+/// [`VALIDATORS`].
+struct TestCli {
+	validators: usize,
+}
+
+impl SubstrateCli for TestCli {
+	fn impl_name() -> String {
+		"Substrate Test Node".into()
+	}
+
+	fn impl_version() -> String {
+		env!("SUBSTRATE_CLI_IMPL_VERSION").into()
+	}
+
+	fn description() -> String {
+		env!("CARGO_PKG_DESCRIPTION").into()
+	}
+
+	fn author() -> String {
+		env!("CARGO_PKG_AUTHORS").into()
+	}
+
+	fn support_url() -> String {
+		"support.anonymous.an".into()
+	}
+
+	fn copyright_start_year() -> i32 {
+		2017
+	}
+
+	fn load_spec(&self, _: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
+		Ok(Box::new(test_network_config(&VALIDATORS[..self.validators])?))
+	}
+
+	fn native_runtime_version(_: &Box<dyn sc_service::ChainSpec>) -> &'static RuntimeVersion {
+		&node_template_runtime::VERSION
+	}
+}
+
+/// A local chain with `validators` as its authorities, endowed accounts, authorized nodes and
+/// named accounts, and Alice as root.
+fn test_network_config(validators: &'static [&'static str]) -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
+
+	Ok(ChainSpec::from_genesis(
+		"Test Network",
+		"test_network",
+		ChainType::Local,
+		move || {
+			testnet_genesis(
+				wasm_binary,
+				validators.iter().copied().map(authority_keys_from_seed).collect(),
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				validators
+					.iter()
+					.map(|v| get_account_id_from_seed::<sr25519::Public>(v))
+					.collect(),
+				// Every validator's node, with the node key `start_node` gives it.
+				validators
+					.iter()
+					.zip(1..)
+					.map(|(v, n)| {
+						(peer_id_from_node_key(n), get_account_id_from_seed::<sr25519::Public>(v))
+					})
+					.collect(),
+				dev_names(validators),
+				vec![],
+				true,
+			)
+		},
+		vec![],
+		None,
+		None,
+		None,
+		None,
+		None,
+	))
+}
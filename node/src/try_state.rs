@@ -0,0 +1,88 @@
+//! The `try-state` subcommand: run the state checks of the native runtime against real chain
+//! state, either scraped from a live node or loaded from a snapshot saved earlier.
+
+use node_template_runtime::{Block, Executive, Hash};
+use remote_externalities::{Builder, Mode, OfflineConfig, OnlineConfig, SnapshotConfig};
+use sc_cli::{CliConfiguration, SharedParams};
+use std::path::PathBuf;
+
+/// Check the invariants of the runtime's pallets against a chain state, optionally after
+/// running the runtime upgrade of the native runtime on it.
+#[derive(Debug, clap::Parser)]
+pub struct TryStateCmd {
+	/// Where to take the state from.
+	#[clap(subcommand)]
+	pub state: State,
+
+	/// Run the runtime upgrade, including its pre and post upgrade checks, before checking the
+	/// state.
+	#[clap(long)]
+	pub on_runtime_upgrade: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// Source of the state to check.
+#[derive(Debug, clap::Subcommand)]
+pub enum State {
+	/// Scrape the state from a live node.
+	Live {
+		/// Websocket endpoint of the node.
+		#[clap(long, default_value = "ws://localhost:9944")]
+		uri: String,
+
+		/// Hash of the block to scrape the state at. Defaults to the latest finalized block.
+		#[clap(long)]
+		at: Option<Hash>,
+
+		/// Save the scraped state to this file, so that it can be checked again offline with
+		/// `snap`.
+		#[clap(long)]
+		snapshot_path: Option<PathBuf>,
+	},
+
+	/// Load the state from a snapshot file saved by `live --snapshot-path`.
+	Snap {
+		/// Path to the snapshot file.
+		#[clap(long)]
+		snapshot_path: PathBuf,
+	},
+}
+
+impl TryStateCmd {
+	/// Build the externalities and run the checks in them.
+	pub async fn run(&self) -> sc_cli::Result<()> {
+		let mode = match &self.state {
+			State::Live { uri, at, snapshot_path } => Mode::Online(OnlineConfig {
+				transport: uri.clone().into(),
+				at: *at,
+				state_snapshot: snapshot_path.clone().map(SnapshotConfig::new),
+				..Default::default()
+			}),
+			State::Snap { snapshot_path } => Mode::Offline(OfflineConfig {
+				state_snapshot: SnapshotConfig::new(snapshot_path.clone()),
+			}),
+		};
+		let mut ext = Builder::<Block>::new().mode(mode).build().await?;
+
+		ext.execute_with(|| -> sc_cli::Result<()> {
+			if self.on_runtime_upgrade {
+				let weight = Executive::try_runtime_upgrade()?;
+				log::info!("Runtime upgrade passed its checks, consuming {} weight.", weight);
+			}
+			node_template_runtime::try_state_all()?;
+			Ok(())
+		})?;
+
+		log::info!("All state checks passed.");
+		Ok(())
+	}
+}
+
+impl CliConfiguration for TryStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
@@ -0,0 +1,250 @@
+//! The `tx` subcommand: build and sign a transaction for any runtime call, then print it or submit
+//! it to a node over RPC.
+
+use crate::extrinsic::{ChainContext, SignedExtrinsicBuilder};
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::{client::ClientT, DeserializeOwned},
+	rpc_params,
+	types::ParamsSer,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use node_template_runtime::{
+	self as runtime, AccountId, Balance, BalancesCall, Hash, Header, Index, SystemCall,
+	TemplateCall,
+};
+use sc_cli::RuntimeVersion;
+use sp_core::{bytes::from_hex, hexdisplay::HexDisplay, sr25519, Bytes, Pair, H256};
+use sp_runtime::traits::Header as _;
+use std::str::FromStr;
+
+/// Build, sign and print or submit a transaction.
+///
+/// For example `tx template do-something 42 --suri //Alice --nonce auto --mortality 64 --submit`.
+#[derive(Debug, clap::Parser)]
+pub struct TxCmd {
+	/// The call to make.
+	#[clap(subcommand)]
+	pub call: CallCmd,
+
+	/// Secret URI of the signer, e.g. `//Alice` or a mnemonic. Required.
+	// Checked in `run`, as clap does not allow global arguments to be required.
+	#[clap(long, global = true)]
+	pub suri: Option<String>,
+
+	/// Nonce of the transaction, or `auto` to ask the node for the next one, counting the
+	/// signer's transactions in its pool.
+	#[clap(long, global = true, default_value = "auto")]
+	pub nonce: Nonce,
+
+	/// Tip for the block author.
+	#[clap(long, global = true, default_value = "0")]
+	pub tip: Balance,
+
+	/// Number of blocks the transaction stays valid for, rounded up to a power of two. `0` makes
+	/// it immortal.
+	#[clap(long, global = true, default_value = "64")]
+	pub mortality: u64,
+
+	/// Websocket endpoint of the node to read the chain state from.
+	#[clap(long, global = true, default_value = "ws://127.0.0.1:9944")]
+	pub url: String,
+
+	/// Submit the transaction instead of printing it.
+	#[clap(long, global = true)]
+	pub submit: bool,
+}
+
+/// Nonce of a transaction.
+#[derive(Debug, Clone, Copy)]
+pub enum Nonce {
+	/// Ask the node for the next nonce.
+	Auto,
+	/// Use the given nonce.
+	Fixed(Index),
+}
+
+impl FromStr for Nonce {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"auto" => Ok(Nonce::Auto),
+			n => n.parse().map(Nonce::Fixed).map_err(|e| format!("Invalid nonce {}: {}", n, e)),
+		}
+	}
+}
+
+/// Calls that can be made with `tx`.
+#[derive(Debug, clap::Subcommand)]
+pub enum CallCmd {
+	/// Calls of `pallet_template`.
+	#[clap(subcommand)]
+	Template(TemplateCallCmd),
+
+	/// Calls of `pallet_balances`.
+	#[clap(subcommand)]
+	Balances(BalancesCallCmd),
+
+	/// Calls of `frame_system`.
+	#[clap(subcommand)]
+	System(SystemCallCmd),
+
+	/// Any runtime call, SCALE encoded as hex.
+	Raw {
+		/// The encoded call.
+		call: String,
+	},
+}
+
+/// Calls of `pallet_template`.
+#[derive(Debug, clap::Subcommand)]
+pub enum TemplateCallCmd {
+	/// Store a value.
+	DoSomething {
+		/// The value to store.
+		something: u32,
+	},
+	/// Increment the stored value.
+	CauseError,
+	/// Commit to a secret for the current round.
+	Commit {
+		/// Hash of the committer's account id and the secret.
+		commitment: H256,
+	},
+	/// Reveal the secret behind an earlier commitment.
+	Reveal {
+		/// The 32 byte secret, as hex.
+		secret: H256,
+	},
+	/// Close the current round and draw a winner.
+	Draw,
+}
+
+/// Calls of `pallet_balances`.
+#[derive(Debug, clap::Subcommand)]
+pub enum BalancesCallCmd {
+	/// Transfer funds without killing the sender's account.
+	TransferKeepAlive {
+		/// SS58 address of the recipient.
+		dest: AccountId,
+		/// Amount to transfer.
+		value: Balance,
+	},
+}
+
+/// Calls of `frame_system`.
+#[derive(Debug, clap::Subcommand)]
+pub enum SystemCallCmd {
+	/// Make an on-chain remark.
+	Remark {
+		/// The remark, as text.
+		remark: String,
+	},
+}
+
+impl CallCmd {
+	/// The runtime call described by the arguments.
+	pub fn to_call(&self) -> Result<runtime::Call, String> {
+		Ok(match self {
+			CallCmd::Template(call) => match call {
+				TemplateCallCmd::DoSomething { something } =>
+					TemplateCall::do_something { something: *something },
+				TemplateCallCmd::CauseError => TemplateCall::cause_error {},
+				TemplateCallCmd::Commit { commitment } =>
+					TemplateCall::commit { commitment: *commitment },
+				TemplateCallCmd::Reveal { secret } =>
+					TemplateCall::reveal { secret: secret.to_fixed_bytes() },
+				TemplateCallCmd::Draw => TemplateCall::draw {},
+			}
+			.into(),
+			CallCmd::Balances(BalancesCallCmd::TransferKeepAlive { dest, value }) =>
+				BalancesCall::transfer_keep_alive { dest: dest.clone().into(), value: *value }
+					.into(),
+			CallCmd::System(SystemCallCmd::Remark { remark }) =>
+				SystemCall::remark { remark: remark.as_bytes().to_vec() }.into(),
+			CallCmd::Raw { call } => {
+				let encoded = from_hex(call).map_err(|e| format!("Invalid hex: {}", e))?;
+				runtime::Call::decode(&mut &encoded[..])
+					.map_err(|e| format!("Invalid call: {}", e))?
+			},
+		})
+	}
+}
+
+impl TxCmd {
+	/// Build the transaction and print or submit it.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let call = self.call.to_call()?;
+		let suri = self.suri.as_deref().ok_or("`--suri` is required")?;
+		let signer = sr25519::Pair::from_string(suri, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+
+		let rt = tokio::runtime::Builder::new_current_thread()
+			.enable_all()
+			.build()
+			.map_err(|e| format!("Failed to start the async runtime: {}", e))?;
+		rt.block_on(async {
+			let client = WsClientBuilder::default()
+				.build(&self.url)
+				.await
+				.map_err(|e| format!("Failed to connect to {}: {}", self.url, e))?;
+
+			let xt = self.build(&client, &signer, call).await?;
+			if self.submit {
+				let hash: Hash =
+					request(&client, "author_submitExtrinsic", rpc_params![Bytes(xt.encode())])
+						.await?;
+				println!("Submitted {:?}", hash);
+			} else {
+				println!("0x{}", HexDisplay::from(&xt.encode()));
+			}
+			Ok(())
+		})
+	}
+
+	async fn build(
+		&self,
+		client: &WsClient,
+		signer: &sr25519::Pair,
+		call: runtime::Call,
+	) -> Result<runtime::UncheckedExtrinsic, String> {
+		let genesis_hash: Hash = request(client, "chain_getBlockHash", rpc_params![0u32]).await?;
+		let best: Header = request(client, "chain_getHeader", rpc_params![]).await?;
+		let version: RuntimeVersion =
+			request(client, "state_getRuntimeVersion", rpc_params![]).await?;
+		let nonce = match self.nonce {
+			Nonce::Fixed(nonce) => nonce,
+			Nonce::Auto => {
+				let who = AccountId::from(signer.public()).to_string();
+				request(client, "system_accountNextIndex", rpc_params![who]).await?
+			},
+		};
+		let builder = SignedExtrinsicBuilder::new(
+			signer.clone(),
+			ChainContext::new(genesis_hash, &version),
+			best.hash(),
+			*best.number(),
+		)
+		.nonce(nonce)
+		.tip(self.tip);
+		let builder = match self.mortality {
+			0 => builder.immortal(),
+			period => builder.mortal(period),
+		};
+
+		Ok(builder.sign(call))
+	}
+}
+
+/// Make the RPC call `method` and decode its result.
+pub(crate) async fn request<R: DeserializeOwned>(
+	client: &WsClient,
+	method: &str,
+	params: Option<ParamsSer<'_>>,
+) -> Result<R, String> {
+	client
+		.request(method, params)
+		.await
+		.map_err(|e| format!("RPC call `{}` failed: {}", method, e))
+}
@@ -0,0 +1,258 @@
+//! The `check-upgrade` subcommand: compare a candidate runtime with the one in the local
+//! database and try its migrations before the upgrade is submitted on chain.
+
+use crate::{
+	decode,
+	metadata_diff::{self, Change},
+	service::FullClient,
+	wasm_runtime::WasmRuntime,
+};
+use codec::Encode;
+use node_template_runtime::{apis::TryStateApi, opaque::Block, BlockWeights, Header, Runtime};
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, RuntimeVersion, SharedParams};
+use sc_client_api::{HeaderBackend, StateBackend};
+use sc_executor::WasmExecutionMethod;
+use sp_api::{Metadata, ProvideRuntimeApi, RuntimeApiInfo};
+use sp_core::{
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, ChildInfo, Storage, StorageChild, StorageMap},
+};
+use sp_io::TestExternalities;
+use sp_runtime::{generic::BlockId, traits::Header as _, RuntimeString};
+use std::{path::PathBuf, sync::Arc};
+
+/// Check a candidate runtime against the runtime of the best block in the local database.
+///
+/// Reports version and metadata changes, then runs the candidate's migrations on a copy of the
+/// best block's state, child tries included. Fails if any change would break the chain or its
+/// users.
+#[derive(Debug, clap::Parser)]
+pub struct CheckUpgradeCmd {
+	/// Path to the candidate runtime, as built by `substrate-wasm-builder`.
+	#[clap(long, value_name = "FILE")]
+	pub wasm: PathBuf,
+
+	/// Fail if the candidate cannot check the state after its migrations, because it was built
+	/// without the `try-runtime` feature. Without this flag a warning is reported instead.
+	#[clap(long)]
+	pub require_try_state: bool,
+
+	/// A storage entry, as `Pallet::Entry`, whose layout changes are converted by a migration of
+	/// the candidate. May be given multiple times.
+	#[clap(long, value_name = "ENTRY")]
+	pub migrated: Vec<String>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl CheckUpgradeCmd {
+	/// Run the checks, printing every change found.
+	pub fn run(
+		&self,
+		client: Arc<FullClient>,
+		wasm_method: WasmExecutionMethod,
+		heap_pages: Option<u64>,
+	) -> sc_cli::Result<()> {
+		let candidate = WasmRuntime::load_with(&self.wasm, wasm_method, heap_pages)?;
+
+		let best = client.info().best_hash;
+		let at = BlockId::Hash(best);
+		let current_version = client
+			.runtime_version_at(&at)
+			.map_err(|e| format!("Failed to read the on-chain runtime version: {}", e))?;
+		let current_metadata = client
+			.runtime_api()
+			.metadata(&at)
+			.map_err(|e| format!("Failed to read the on-chain metadata: {}", e))?;
+		let candidate_version = candidate.version()?;
+
+		println!("Checking {} against {} at block {}", candidate_version, current_version, best);
+		let mut changes = compare_versions(&current_version, &candidate_version);
+		let metadata_changes = metadata_diff::diff(
+			&decode::metadata(&current_metadata)?,
+			&candidate.metadata()?,
+			&self.migrated,
+		);
+		changes.extend(metadata_diff::check_transaction_version(
+			&metadata_changes,
+			current_version.transaction_version,
+			candidate_version.transaction_version,
+		));
+		changes.extend(metadata_changes);
+		changes.extend(try_migrations(
+			&candidate,
+			&client,
+			&at,
+			&current_version,
+			&candidate_version,
+			self.require_try_state,
+		)?);
+
+		metadata_diff::report(&changes)
+	}
+}
+
+impl CliConfiguration for CheckUpgradeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Compare the versions of the current and the candidate runtime.
+fn compare_versions(current: &RuntimeVersion, candidate: &RuntimeVersion) -> Vec<Change> {
+	let mut changes = Vec::new();
+
+	if candidate.spec_name != current.spec_name {
+		changes.push(Change::breaking(format!(
+			"spec_name changed from {} to {}, `set_code` will reject the upgrade",
+			current.spec_name, candidate.spec_name
+		)));
+	}
+	if candidate.spec_version <= current.spec_version {
+		changes.push(Change::breaking(format!(
+			"spec_version {} is not greater than the on-chain {}, `set_code` will reject it",
+			candidate.spec_version, current.spec_version
+		)));
+	}
+	if candidate.transaction_version < current.transaction_version {
+		changes.push(Change::breaking(format!(
+			"transaction_version went down from {} to {}",
+			current.transaction_version, candidate.transaction_version
+		)));
+	} else if candidate.transaction_version > current.transaction_version {
+		changes.push(Change::note(format!(
+			"transaction_version bumped from {} to {}, signed transactions must be rebuilt",
+			current.transaction_version, candidate.transaction_version
+		)));
+	}
+
+	for (id, version) in current.apis.iter() {
+		match candidate.api_version(id) {
+			None => changes
+				.push(Change::breaking(format!("runtime API 0x{} removed", HexDisplay::from(id)))),
+			Some(new) if new != *version => changes.push(Change::note(format!(
+				"runtime API 0x{} changed from version {} to {}",
+				HexDisplay::from(id),
+				version,
+				new
+			))),
+			Some(_) => {},
+		}
+	}
+	for (id, _) in candidate.apis.iter().filter(|(id, _)| current.api_version(id).is_none()) {
+		changes.push(Change::note(format!("runtime API 0x{} added", HexDisplay::from(id))));
+	}
+
+	changes
+}
+
+/// Copy the state at `at`, including the default child tries.
+fn copy_state(client: &FullClient, at: &BlockId<Block>) -> Result<Storage, String> {
+	let state = client.state_at(at).map_err(|e| format!("Failed to read the state: {}", e))?;
+	let mut storage = Storage::default();
+	for (key, value) in state.pairs() {
+		let child_key = match key.strip_prefix(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			Some(child_key) => child_key,
+			// The roots of child tries are recomputed from their content.
+			None => {
+				storage.top.insert(key, value);
+				continue
+			},
+		};
+		let child_info = ChildInfo::new_default(child_key);
+		let mut data = StorageMap::new();
+		for child_key in state.child_keys(&child_info, &[]) {
+			let value = state
+				.child_storage(&child_info, &child_key)
+				.map_err(|e| format!("Failed to read a child trie: {}", e))?
+				.unwrap_or_default();
+			data.insert(child_key, value);
+		}
+		storage
+			.children_default
+			.insert(child_info.storage_key().to_vec(), StorageChild { data, child_info });
+	}
+	Ok(storage)
+}
+
+/// Initialize a block on top of `at` with the candidate in place, which runs its migrations,
+/// on a copy of the state. The chain itself is left untouched.
+///
+/// The state is checked afterwards if the candidate was built with `try-runtime`. Otherwise the
+/// skipped checks are reported, as breaking if `require_try_state` is set.
+fn try_migrations(
+	candidate: &WasmRuntime,
+	client: &FullClient,
+	at: &BlockId<Block>,
+	current_version: &RuntimeVersion,
+	candidate_version: &RuntimeVersion,
+	require_try_state: bool,
+) -> Result<Vec<Change>, String> {
+	let storage = copy_state(client, at)?;
+	let mut ext =
+		TestExternalities::new_with_state_version(storage, current_version.state_version());
+	ext.insert(well_known_keys::CODE.to_vec(), candidate.code().to_vec());
+
+	let parent = client
+		.header(*at)
+		.map_err(|e| format!("Failed to read the best header: {}", e))?
+		.ok_or("Best header is missing")?;
+	let header = Header::new(
+		parent.number() + 1,
+		Default::default(),
+		Default::default(),
+		parent.hash(),
+		Default::default(),
+	);
+
+	let mut changes = Vec::new();
+	if let Err(e) = candidate.call::<()>(&mut ext, "Core_initialize_block", &header.encode()) {
+		changes.push(Change::breaking(format!("migrations failed: {}", e)));
+		return Ok(changes)
+	}
+
+	let weight = ext.execute_with(|| frame_system::Pallet::<Runtime>::block_weight().total());
+	let max_weight = BlockWeights::get().max_block;
+	let message = format!("migrations used {} of the maximum block weight {}", weight, max_weight);
+	changes.push(if weight > max_weight {
+		Change::breaking(message)
+	} else {
+		Change::note(message)
+	});
+
+	if candidate_version.has_api_with(&<dyn TryStateApi<Block>>::ID, |_| true) {
+		let checks: Result<(), RuntimeString> =
+			candidate.call(&mut ext, "TryStateApi_try_state", &[])?;
+		if let Err(e) = checks {
+			changes.push(Change::breaking(format!("state checks failed after migrations: {}", e)));
+		}
+	} else {
+		let message = "state checks skipped, the candidate was built without `try-runtime`";
+		log::warn!("{}", message);
+		changes.push(if require_try_state {
+			Change::breaking(message)
+		} else {
+			Change::note(message)
+		});
+	}
+
+	Ok(changes)
+}
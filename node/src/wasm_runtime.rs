@@ -0,0 +1,76 @@
+//! Calling into a runtime blob read from disk, outside of any client.
+
+use crate::{decode, service::ExecutorDispatch};
+use codec::Decode;
+use frame_metadata::RuntimeMetadataV14;
+use sc_cli::RuntimeVersion;
+use sc_executor::{NativeExecutionDispatch, WasmExecutionMethod, WasmExecutor};
+use sc_executor_common::runtime_blob::RuntimeBlob;
+use sp_core::OpaqueMetadata;
+use sp_io::TestExternalities;
+use std::path::Path;
+
+/// Host functions available to the runtime, the same as for the node's executor.
+type HostFunctions = (
+	sp_io::SubstrateHostFunctions,
+	<ExecutorDispatch as NativeExecutionDispatch>::ExtendHostFunctions,
+);
+
+/// A runtime blob together with an executor for it.
+pub struct WasmRuntime {
+	executor: WasmExecutor<HostFunctions>,
+	blob: RuntimeBlob,
+	code: Vec<u8>,
+}
+
+impl WasmRuntime {
+	/// Read the runtime at `path`, as built by `substrate-wasm-builder`, compressed or not.
+	pub fn load(path: &Path) -> Result<Self, String> {
+		Self::load_with(path, WasmExecutionMethod::Interpreted, None)
+	}
+
+	/// Read the runtime at `path` and execute it with `method`.
+	pub fn load_with(
+		path: &Path,
+		method: WasmExecutionMethod,
+		heap_pages: Option<u64>,
+	) -> Result<Self, String> {
+		let code =
+			std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+		let blob = RuntimeBlob::uncompress_if_needed(&code)
+			.map_err(|e| format!("Invalid runtime {}: {}", path.display(), e))?;
+		Ok(Self { executor: WasmExecutor::new(method, heap_pages, 1, None, 1), blob, code })
+	}
+
+	/// The code as read from disk, to be put under `:code`.
+	pub fn code(&self) -> &[u8] {
+		&self.code
+	}
+
+	/// Call the runtime API `method` in `ext` and decode the result.
+	pub fn call<R: Decode>(
+		&self,
+		ext: &mut TestExternalities,
+		method: &str,
+		data: &[u8],
+	) -> Result<R, String> {
+		let result = self
+			.executor
+			.uncached_call(self.blob.clone(), &mut ext.ext(), true, method, data)
+			.map_err(|e| format!("Calling `{}` failed: {}", method, e))?;
+		R::decode(&mut &result[..])
+			.map_err(|e| format!("Failed to decode the result of `{}`: {}", method, e))
+	}
+
+	/// The version of the runtime.
+	pub fn version(&self) -> Result<RuntimeVersion, String> {
+		self.call(&mut TestExternalities::default(), "Core_version", &[])
+	}
+
+	/// The decoded metadata of the runtime.
+	pub fn metadata(&self) -> Result<RuntimeMetadataV14, String> {
+		let metadata: OpaqueMetadata =
+			self.call(&mut TestExternalities::default(), "Metadata_metadata", &[])?;
+		decode::metadata(&metadata)
+	}
+}
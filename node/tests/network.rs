@@ -0,0 +1,136 @@
+//! End-to-end tests on an in-process network of validators.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	storage::{storage_prefix, StorageValue},
+	StorageHasher, Twox64Concat,
+};
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClient};
+use node_template::{
+	extrinsic::{ChainContext, SignedExtrinsicBuilder},
+	rpc::dry_run::{DryRunOutcome, DryRunReport},
+	submit::KEY_TYPE,
+	testing::TestNetwork,
+};
+use node_template_runtime::{
+	pallet_template, AccountId, Hash, Header, Runtime, TemplateCall, UncheckedExtrinsic,
+};
+use sc_cli::RuntimeVersion;
+use sp_core::{
+	storage::{well_known_keys, StorageKey},
+	Bytes,
+};
+use sp_keyring::Sr25519Keyring;
+use sp_keystore::SyncCryptoStore;
+use sp_runtime::traits::Header as _;
+use std::{collections::BTreeSet, time::Duration};
+
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// `call` signed by Alice with her first nonce, at the best block of the node of `client`.
+async fn signed_by_alice(client: &WsClient, call: TemplateCall<Runtime>) -> UncheckedExtrinsic {
+	let genesis_hash: Hash = client.request("chain_getBlockHash", rpc_params![0u32]).await.unwrap();
+	let version: RuntimeVersion =
+		client.request("state_getRuntimeVersion", rpc_params![]).await.unwrap();
+	let best: Header = client.request("chain_getHeader", rpc_params![]).await.unwrap();
+	SignedExtrinsicBuilder::new(
+		Sr25519Keyring::Alice.pair(),
+		ChainContext::new(genesis_hash, &version),
+		best.hash(),
+		best.number,
+	)
+	.sign(call)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn validators_finalize_blocks_and_share_state() {
+	let network = TestNetwork::start(2).await.unwrap();
+	let (alice, bob) = (&network.nodes()[0], &network.nodes()[1]);
+
+	// Both validators take part in GRANDPA, so finality keeps advancing.
+	network.wait_for_finalized(3, TIMEOUT).await.unwrap();
+
+	// A call submitted to Alice's node is executed on Bob's as well.
+	let client = alice.rpc().await.unwrap();
+	let xt = signed_by_alice(&client, TemplateCall::do_something { something: 42 }).await;
+	let _: Hash = client
+		.request("author_submitExtrinsic", rpc_params![Bytes(xt.encode())])
+		.await
+		.unwrap();
+
+	let client = bob.rpc().await.unwrap();
+	let key = StorageKey(pallet_template::Something::<Runtime>::hashed_key().to_vec());
+	let start = std::time::Instant::now();
+	loop {
+		let value: Option<Bytes> =
+			client.request("state_getStorage", rpc_params![key.clone()]).await.unwrap();
+		if let Some(value) = value {
+			assert_eq!(u32::decode(&mut &value[..]).unwrap(), 42);
+			break
+		}
+		assert!(start.elapsed() < TIMEOUT, "call not executed on Bob's node");
+		tokio::time::sleep(Duration::from_millis(500)).await;
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn submitter_picks_consecutive_nonces() {
+	let network = TestNetwork::start(1).await.unwrap();
+	let node = &network.nodes()[0];
+	let alice = SyncCryptoStore::sr25519_generate_new(&*node.keystore(), KEY_TYPE, Some("//Alice"))
+		.unwrap();
+
+	// The second transaction is submitted while the first is still in the pool, so it only gets
+	// the next nonce if the submitter counts the first one.
+	let submitter = node.submitter();
+	let first = submitter
+		.submit(&alice, TemplateCall::do_something { something: 1 })
+		.await
+		.unwrap();
+	let second = submitter
+		.submit(&alice, TemplateCall::do_something { something: 2 })
+		.await
+		.unwrap();
+	assert_eq!(submitter.next_nonce(&AccountId::from(alice)).unwrap(), 2);
+
+	assert_eq!(first.in_block().await.unwrap().result, Ok(()));
+	assert_eq!(second.in_block().await.unwrap().result, Ok(()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dry_run_reports_only_the_keys_its_extrinsic_wrote() {
+	let network = TestNetwork::start(1).await.unwrap();
+	let client = network.nodes()[0].rpc().await.unwrap();
+	let xt = signed_by_alice(&client, TemplateCall::do_something { something: 42 }).await;
+
+	let report: DryRunReport = client
+		.request("system_dryRunDecoded", rpc_params![Bytes(xt.encode()), None::<Hash>])
+		.await
+		.unwrap();
+	assert_eq!(report.result, DryRunOutcome::Success);
+
+	let touched: BTreeSet<Vec<u8>> = report.storage_keys.into_iter().map(|key| key.0).collect();
+	let value =
+		|pallet: &str, item: &str| storage_prefix(pallet.as_bytes(), item.as_bytes()).to_vec();
+	// The extrinsic is the first of the block.
+	let extrinsic_data =
+		[value("System", "ExtrinsicData"), 0u32.using_encoded(Twox64Concat::hash)].concat();
+	// Block initialization also writes `Number`, `ParentHash`, `Digest` and resets
+	// `BlockActivity`, among others, which must not show up unless the extrinsic wrote them.
+	let expected: BTreeSet<Vec<u8>> = [
+		frame_system::Account::<Runtime>::hashed_key_for(Sr25519Keyring::Alice.to_account_id()),
+		value("Balances", "TotalIssuance"),
+		value("TemplateModule", "Something"),
+		value("TemplateModule", "BlockActivity"),
+		extrinsic_data,
+		value("System", "AllExtrinsicsLen"),
+		value("System", "BlockWeight"),
+		value("System", "Events"),
+		value("System", "EventCount"),
+		value("System", "ExecutionPhase"),
+		well_known_keys::EXTRINSIC_INDEX.to_vec(),
+	]
+	.into_iter()
+	.collect();
+	assert_eq!(touched, expected);
+}
@@ -0,0 +1,198 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! An allowlist of the accounts that may sign transactions, managed by an admin origin.
+//!
+//! The pallet only keeps the list. It is enforced by the [`CheckAllowlist`] signed extension,
+//! which the runtime adds to its `SignedExtra`. Unsigned transactions are not affected: pallets
+//! that accept them on behalf of an account can check the list through the `Contains`
+//! implementation of [`Pallet`].
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use frame_support::{
+	dispatch::DispatchInfo,
+	sp_std::{fmt, marker::PhantomData},
+};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, Dispatchable, SignedExtension},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+};
+
+/// `InvalidTransaction::Custom` code of transactions signed by an account that is not on the
+/// allowlist.
+pub const NOT_ALLOWLISTED: u8 = 2;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{pallet_prelude::*, sp_std::vec::Vec, traits::Contains};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to add accounts to and remove accounts from the allowlist.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+	}
+
+	/// Accounts allowed to sign transactions.
+	#[pallet::storage]
+	pub type Allowlist<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// Accounts on the allowlist at genesis. Include the accounts that manage the allowlist,
+		/// or nobody will be able to sign a transaction.
+		pub accounts: Vec<T::AccountId>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { accounts: Vec::new() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for who in &self.accounts {
+				<Allowlist<T>>::insert(who, ());
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An account was added to the allowlist. [who]
+		Added(T::AccountId),
+		/// An account was removed from the allowlist. [who]
+		Removed(T::AccountId),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account is already on the allowlist.
+		AlreadyAllowlisted,
+		/// The account is not on the allowlist.
+		NotAllowlisted,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Allow `who` to sign transactions.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn add(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(!<Allowlist<T>>::contains_key(&who), Error::<T>::AlreadyAllowlisted);
+
+			<Allowlist<T>>::insert(&who, ());
+			Self::deposit_event(Event::Added(who));
+			Ok(())
+		}
+
+		/// Stop `who` from signing transactions. Transactions of `who` already in the pool are
+		/// dropped when they are revalidated.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn remove(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(<Allowlist<T>>::contains_key(&who), Error::<T>::NotAllowlisted);
+
+			<Allowlist<T>>::remove(&who);
+			Self::deposit_event(Event::Removed(who));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether `who` may sign transactions.
+		pub fn is_allowlisted(who: &T::AccountId) -> bool {
+			<Allowlist<T>>::contains_key(who)
+		}
+	}
+
+	impl<T: Config> Contains<T::AccountId> for Pallet<T> {
+		fn contains(who: &T::AccountId) -> bool {
+			Self::is_allowlisted(who)
+		}
+	}
+}
+
+/// Rejects transactions whose signer is not on the allowlist, with
+/// `InvalidTransaction::Custom(NOT_ALLOWLISTED)`.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckAllowlist<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckAllowlist<T> {
+	/// Create a new instance.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckAllowlist<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> fmt::Debug for CheckAllowlist<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "CheckAllowlist")
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckAllowlist<T>
+where
+	T::Call: Dispatchable<Info = DispatchInfo>,
+{
+	const IDENTIFIER: &'static str = "CheckAllowlist";
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		_call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		if Pallet::<T>::is_allowlisted(who) {
+			Ok(ValidTransaction::default())
+		} else {
+			Err(InvalidTransaction::Custom(NOT_ALLOWLISTED).into())
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
+	}
+}
@@ -0,0 +1,68 @@
+use crate::{mock::*, CheckAllowlist, Error, Event, NOT_ALLOWLISTED};
+use frame_support::{assert_noop, assert_ok, dispatch::DispatchInfo, traits::Contains};
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	DispatchError,
+};
+
+fn validate(who: u64) -> Result<(), TransactionValidityError> {
+	let call = Call::System(frame_system::Call::remark { remark: vec![] });
+	CheckAllowlist::<Test>::new()
+		.validate(&who, &call, &DispatchInfo::default(), 0)
+		.map(|_| ())
+}
+
+#[test]
+fn genesis_accounts_are_allowlisted() {
+	new_test_ext().execute_with(|| {
+		assert!(Allowlist::is_allowlisted(&1));
+		assert!(Allowlist::is_allowlisted(&2));
+		assert!(!Allowlist::is_allowlisted(&3));
+		assert!(<Allowlist as Contains<u64>>::contains(&1));
+		assert!(!<Allowlist as Contains<u64>>::contains(&3));
+	});
+}
+
+#[test]
+fn root_manages_the_allowlist() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Allowlist::add(Origin::root(), 3));
+		assert!(Allowlist::is_allowlisted(&3));
+		assert_eq!(System::events().pop().unwrap().event, Event::Added(3).into());
+
+		assert_ok!(Allowlist::remove(Origin::root(), 1));
+		assert!(!Allowlist::is_allowlisted(&1));
+		assert_eq!(System::events().pop().unwrap().event, Event::Removed(1).into());
+
+		assert_noop!(Allowlist::add(Origin::root(), 3), Error::<Test>::AlreadyAllowlisted);
+		assert_noop!(Allowlist::remove(Origin::root(), 1), Error::<Test>::NotAllowlisted);
+	});
+}
+
+#[test]
+fn only_root_manages_the_allowlist() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Allowlist::add(Origin::signed(1), 3), DispatchError::BadOrigin);
+		assert_noop!(Allowlist::remove(Origin::signed(1), 2), DispatchError::BadOrigin);
+	});
+}
+
+#[test]
+fn check_allowlist_rejects_other_signers() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(validate(1), Ok(()));
+		assert_eq!(
+			validate(3),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(NOT_ALLOWLISTED)))
+		);
+
+		assert_ok!(Allowlist::add(Origin::root(), 3));
+		assert_eq!(validate(3), Ok(()));
+		assert_ok!(Allowlist::remove(Origin::root(), 1));
+		assert_eq!(
+			validate(1),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(NOT_ALLOWLISTED)))
+		);
+	});
+}
@@ -0,0 +1,221 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Human-readable names for accounts.
+//!
+//! Accounts set their own name, reserving a deposit for as long as they hold it. The judge
+//! origin, root in the node template runtime, judges whether names are fit, and can remove a name
+//! and slash its deposit. Names given at genesis are held without a deposit and judged
+//! `KnownGood`.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		pallet_prelude::*,
+		sp_std::vec::Vec,
+		traits::{Currency, OnUnbalanced, ReservableCurrency},
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{Saturating, Zero};
+
+	pub(crate) type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::NegativeImbalance;
+
+	/// The name stored for an account.
+	pub type NameFor<T> = Nickname<BoundedVec<u8, <T as Config>::MaxLength>, BalanceOf<T>>;
+
+	/// How the judge origin judged a name.
+	#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub enum Judgement {
+		/// Not judged since it was last set.
+		Unknown,
+		/// Fit for use, without further checks.
+		Reasonable,
+		/// Checked to name the operator behind the account.
+		KnownGood,
+		/// Misleading or otherwise unfit.
+		Erroneous,
+	}
+
+	/// A name together with the deposit reserved for it and its judgement.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct Nickname<Name, Balance> {
+		/// The name, usually UTF-8.
+		pub name: Name,
+		/// Amount reserved from the account, returned when it clears the name.
+		pub deposit: Balance,
+		/// The latest judgement of the name.
+		pub judgement: Judgement,
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency in which name deposits are reserved.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Deposit reserved for holding a name.
+		#[pallet::constant]
+		type NameDeposit: Get<BalanceOf<Self>>;
+
+		/// Minimum length of a name, in bytes.
+		#[pallet::constant]
+		type MinLength: Get<u32>;
+
+		/// Maximum length of a name, in bytes.
+		#[pallet::constant]
+		type MaxLength: Get<u32>;
+
+		/// Origin allowed to judge names and to remove them.
+		type JudgeOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Handler of the deposits slashed when a name is removed by the judge origin.
+		type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	}
+
+	/// The name of each account that has one.
+	#[pallet::storage]
+	#[pallet::getter(fn name_of)]
+	pub type NameOf<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, NameFor<T>>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// Names given at genesis, without a deposit.
+		pub names: Vec<(T::AccountId, Vec<u8>)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { names: Vec::new() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (who, name) in &self.names {
+				let name = Pallet::<T>::bound(name.clone()).expect("genesis names fit the bounds");
+				let nickname =
+					Nickname { name, deposit: Zero::zero(), judgement: Judgement::KnownGood };
+				<NameOf<T>>::insert(who, nickname);
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An account set its name. [who]
+		NameSet(T::AccountId),
+		/// An account cleared its name and got its deposit back. [who, deposit]
+		NameCleared(T::AccountId, BalanceOf<T>),
+		/// A name was removed by the judge origin and its deposit slashed. [who, deposit]
+		NameKilled(T::AccountId, BalanceOf<T>),
+		/// A name was judged. [who, judgement]
+		JudgementGiven(T::AccountId, Judgement),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The name is shorter than `Config::MinLength`.
+		TooShort,
+		/// The name is longer than `Config::MaxLength`.
+		TooLong,
+		/// The account has no name.
+		Unnamed,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the name of the caller, reserving `Config::NameDeposit` if it has none yet.
+		///
+		/// A new name has to be judged again.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn set_name(origin: OriginFor<T>, name: Vec<u8>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let name = Self::bound(name)?;
+
+			// Names given at genesis hold no deposit, so renaming them reserves one.
+			let held = <NameOf<T>>::get(&who).map_or_else(Zero::zero, |nickname| nickname.deposit);
+			let deposit = T::NameDeposit::get().max(held);
+			T::Currency::reserve(&who, deposit.saturating_sub(held))?;
+
+			<NameOf<T>>::insert(&who, Nickname { name, deposit, judgement: Judgement::Unknown });
+			Self::deposit_event(Event::NameSet(who));
+			Ok(())
+		}
+
+		/// Clear the name of the caller and return its deposit.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn clear_name(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let nickname = <NameOf<T>>::take(&who).ok_or(Error::<T>::Unnamed)?;
+
+			T::Currency::unreserve(&who, nickname.deposit);
+			Self::deposit_event(Event::NameCleared(who, nickname.deposit));
+			Ok(())
+		}
+
+		/// Judge the name of `who`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn provide_judgement(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			judgement: Judgement,
+		) -> DispatchResult {
+			T::JudgeOrigin::ensure_origin(origin)?;
+			<NameOf<T>>::try_mutate(&who, |nickname| {
+				nickname.as_mut().map(|n| n.judgement = judgement).ok_or(Error::<T>::Unnamed)
+			})?;
+
+			Self::deposit_event(Event::JudgementGiven(who, judgement));
+			Ok(())
+		}
+
+		/// Remove the name of `who` and slash its deposit.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn kill_name(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::JudgeOrigin::ensure_origin(origin)?;
+			let nickname = <NameOf<T>>::take(&who).ok_or(Error::<T>::Unnamed)?;
+
+			let (slashed, _) = T::Currency::slash_reserved(&who, nickname.deposit);
+			T::Slashed::on_unbalanced(slashed);
+			Self::deposit_event(Event::NameKilled(who, nickname.deposit));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The name of `who`, its deposit and its judgement, for the runtime API.
+		pub fn identity_of(who: &T::AccountId) -> Option<Nickname<Vec<u8>, BalanceOf<T>>> {
+			<NameOf<T>>::get(who).map(|nickname| Nickname {
+				name: nickname.name.into_inner(),
+				deposit: nickname.deposit,
+				judgement: nickname.judgement,
+			})
+		}
+
+		/// Check `name` against the length bounds.
+		fn bound(name: Vec<u8>) -> Result<BoundedVec<u8, T::MaxLength>, Error<T>> {
+			ensure!(name.len() >= T::MinLength::get() as usize, Error::<T>::TooShort);
+			name.try_into().map_err(|_| Error::<T>::TooLong)
+		}
+	}
+}
@@ -0,0 +1,93 @@
+use crate::{mock::*, Error, Event, Judgement, Nickname};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+fn nickname(name: &[u8], deposit: u64, judgement: Judgement) -> Option<Nickname<Vec<u8>, u64>> {
+	Some(Nickname { name: name.to_vec(), deposit, judgement })
+}
+
+#[test]
+fn genesis_names_hold_no_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Nicknames::identity_of(&3), nickname(b"genesis", 0, Judgement::KnownGood));
+		assert_eq!(Balances::reserved_balance(3), 0);
+
+		// Renaming reserves the deposit like any other name.
+		assert_ok!(Nicknames::set_name(Origin::signed(3), b"renamed".to_vec()));
+		assert_eq!(Nicknames::identity_of(&3), nickname(b"renamed", 10, Judgement::Unknown));
+		assert_eq!(Balances::reserved_balance(3), 10);
+	});
+}
+
+#[test]
+fn names_are_held_against_a_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nicknames::set_name(Origin::signed(1), b"alice".to_vec()));
+		assert_eq!(Nicknames::identity_of(&1), nickname(b"alice", 10, Judgement::Unknown));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_eq!(System::events().pop().unwrap().event, Event::NameSet(1).into());
+
+		// Renaming keeps the deposit.
+		assert_ok!(Nicknames::set_name(Origin::signed(1), b"alicia".to_vec()));
+		assert_eq!(Balances::reserved_balance(1), 10);
+
+		assert_ok!(Nicknames::clear_name(Origin::signed(1)));
+		assert_eq!(Nicknames::identity_of(&1), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), ENDOWMENT);
+		assert_noop!(Nicknames::clear_name(Origin::signed(1)), Error::<Test>::Unnamed);
+	});
+}
+
+#[test]
+fn names_must_fit_the_bounds() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Nicknames::set_name(Origin::signed(1), b"al".to_vec()),
+			Error::<Test>::TooShort
+		);
+		assert_noop!(
+			Nicknames::set_name(Origin::signed(1), b"alexandra".to_vec()),
+			Error::<Test>::TooLong
+		);
+	});
+}
+
+#[test]
+fn root_judges_names_until_they_change() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nicknames::set_name(Origin::signed(1), b"alice".to_vec()));
+		assert_noop!(
+			Nicknames::provide_judgement(Origin::signed(2), 1, Judgement::KnownGood),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Nicknames::provide_judgement(Origin::root(), 2, Judgement::KnownGood),
+			Error::<Test>::Unnamed
+		);
+
+		assert_ok!(Nicknames::provide_judgement(Origin::root(), 1, Judgement::KnownGood));
+		assert_eq!(Nicknames::identity_of(&1), nickname(b"alice", 10, Judgement::KnownGood));
+		assert_eq!(
+			System::events().pop().unwrap().event,
+			Event::JudgementGiven(1, Judgement::KnownGood).into()
+		);
+
+		assert_ok!(Nicknames::set_name(Origin::signed(1), b"mallory".to_vec()));
+		assert_eq!(Nicknames::identity_of(&1), nickname(b"mallory", 10, Judgement::Unknown));
+	});
+}
+
+#[test]
+fn root_kills_names_and_slashes_their_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nicknames::set_name(Origin::signed(1), b"alice".to_vec()));
+		assert_noop!(Nicknames::kill_name(Origin::signed(2), 1), DispatchError::BadOrigin);
+
+		assert_ok!(Nicknames::kill_name(Origin::root(), 1));
+		assert_eq!(Nicknames::identity_of(&1), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), ENDOWMENT - 10);
+		assert_eq!(System::events().pop().unwrap().event, Event::NameKilled(1, 10).into());
+	});
+}
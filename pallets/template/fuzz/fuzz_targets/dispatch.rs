@@ -0,0 +1,61 @@
+//! Feed arbitrary encoded `TemplateModule` calls through the mock runtime.
+//!
+//! The input is decoded as a sequence of `(control, call)` pairs. The low bits of `control` pick
+//! the origin, and its top bit advances the chain by one block before dispatching. After every
+//! call the target checks that failed calls left storage untouched and that the pallet never
+//! creates or destroys balance.
+//!
+//! Run with `cargo fuzz run dispatch` from `pallets/template`.
+
+#![no_main]
+
+use codec::DecodeLimit;
+use frame_support::traits::Currency;
+use libfuzzer_sys::fuzz_target;
+use pallet_template::mock::{new_test_ext, run_to_block, Balances, Call, Origin, System, Test};
+use sp_runtime::{traits::Dispatchable, StateVersion};
+
+/// Nesting limit when decoding calls; the pallet's calls are flat.
+const MAX_DEPTH: u32 = 8;
+
+/// Upper bound on calls per input, to keep runs short.
+const MAX_CALLS: usize = 256;
+
+fn origin(control: u8) -> Origin {
+	match control & 0x7f {
+		0 => Origin::root(),
+		1 => Origin::none(),
+		// Accounts `1..=4` are endowed, `5` is not.
+		n => Origin::signed(u64::from(n % 5) + 1),
+	}
+}
+
+fuzz_target!(|data: &[u8]| {
+	let calls = match Vec::<(u8, pallet_template::Call<Test>)>::decode_with_depth_limit(
+		MAX_DEPTH,
+		&mut &data[..],
+	) {
+		Ok(calls) => calls,
+		Err(_) => return,
+	};
+
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+
+		for (control, call) in calls.into_iter().take(MAX_CALLS) {
+			if control & 0x80 != 0 {
+				run_to_block(System::block_number() + 1);
+			}
+
+			let root = sp_io::storage::root(StateVersion::V1);
+			if Call::TemplateModule(call).dispatch(origin(control)).is_err() {
+				assert_eq!(
+					root,
+					sp_io::storage::root(StateVersion::V1),
+					"failed call changed storage"
+				);
+			}
+			assert_eq!(Balances::total_issuance(), issuance);
+		}
+	});
+});
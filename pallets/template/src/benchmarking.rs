@@ -4,8 +4,41 @@ use super::*;
 
 #[allow(unused)]
 use crate::Pallet as Template;
-use frame_benchmarking::{benchmarks, whitelisted_caller};
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::{
+	traits::{Currency, Hooks},
+	weights::Weight,
+};
 use frame_system::RawOrigin;
+use sp_runtime::traits::{Bounded, Hash, One, Zero};
+
+/// Create an account able to pay any commitment deposit.
+fn funded_account<T: Config>(name: &'static str, index: u32) -> T::AccountId {
+	let who: T::AccountId = account(name, index, 0);
+	T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value() / 2u32.into());
+	who
+}
+
+/// Open a round and fill it with `n` commitments, the first `r` of which are revealed.
+///
+/// Leaves the chain at the first block of the reveal period. No round is opened if `n` is zero.
+fn setup_round<T: Config>(n: u32, r: u32) {
+	frame_system::Pallet::<T>::set_block_number(1u32.into());
+	if n == 0 {
+		return
+	}
+	for i in 0..n {
+		let who = funded_account::<T>("committer", i);
+		let commitment = T::Hashing::hash_of(&(&who, [i as u8; 32]));
+		Template::<T>::commit(RawOrigin::Signed(who).into(), commitment).unwrap();
+	}
+	let round = CurrentRound::<T>::get().unwrap();
+	frame_system::Pallet::<T>::set_block_number(round.commit_end + 1u32.into());
+	for i in 0..r {
+		let who: T::AccountId = account("committer", i, 0);
+		Template::<T>::reveal(RawOrigin::Signed(who).into(), [i as u8; 32]).unwrap();
+	}
+}
 
 benchmarks! {
 	do_something {
@@ -16,5 +49,100 @@ benchmarks! {
 		assert_eq!(Something::<T>::get(), Some(s));
 	}
 
+	cause_error {
+		Something::<T>::put(1);
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller))
+	verify {
+		assert_eq!(Something::<T>::get(), Some(2));
+	}
+
+	// The signature is checked with the runtime's signature scheme.
+	do_something_feeless {
+		let who = T::BenchmarkHelper::feeless_signer();
+		let (era, index) = Template::<T>::feeless_calls(&who);
+		let payload = Template::<T>::feeless_payload(42, &who, era, index);
+		let signature = T::BenchmarkHelper::sign(&who, &payload);
+	}: _(RawOrigin::None, 42, who.clone(), era, index, signature)
+	verify {
+		assert_eq!(Template::<T>::feeless_calls(&who), (era, index + 1));
+	}
+
+	// The caller is not whitelisted, so that the reads and writes of its account by the deposit
+	// are weighed.
+	commit {
+		let caller = funded_account::<T>("caller", 0);
+		let commitment = T::Hashing::hash_of(&(&caller, [0u8; 32]));
+	}: _(RawOrigin::Signed(caller.clone()), commitment)
+	verify {
+		assert_eq!(Commitments::<T>::get(0, &caller).map(|c| c.hash), Some(commitment));
+	}
+
+	reveal {
+		let caller = funded_account::<T>("caller", 0);
+		let commitment = T::Hashing::hash_of(&(&caller, [0u8; 32]));
+		Template::<T>::commit(RawOrigin::Signed(caller.clone()).into(), commitment)?;
+		let round = CurrentRound::<T>::get().unwrap();
+		frame_system::Pallet::<T>::set_block_number(round.commit_end + 1u32.into());
+	}: _(RawOrigin::Signed(caller.clone()), [0u8; 32])
+	verify {
+		assert_eq!(Revealed::<T>::get().first(), Some(&caller));
+	}
+
+	draw {
+		let n in 1 .. T::MaxParticipants::get();
+		setup_round::<T>(n, n / 2);
+		let round = CurrentRound::<T>::get().unwrap();
+		frame_system::Pallet::<T>::set_block_number(round.reveal_end + 1u32.into());
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller))
+	verify {
+		assert!(CurrentRound::<T>::get().is_none());
+	}
+
+	on_initialize {
+		BlockActivity::<T>::put(1);
+		FeelessCallsInBlock::<T>::put(1);
+	}: {
+		Template::<T>::on_initialize(1u32.into());
+	}
+	verify {
+		assert_eq!(BlockActivity::<T>::get(), 0);
+		assert_eq!(FeelessCallsInBlock::<T>::get(), 0);
+	}
+
+	// The cost of `on_idle` removing `n` unrevealed commitments of a closed round.
+	remove_stale_commitments {
+		let n in 0 .. T::MaxParticipants::get();
+		setup_round::<T>(n, 0);
+		if let Some(round) = CurrentRound::<T>::get() {
+			frame_system::Pallet::<T>::set_block_number(round.reveal_end + 1u32.into());
+			let caller: T::AccountId = whitelisted_caller();
+			Template::<T>::draw(RawOrigin::Signed(caller).into())?;
+		}
+	}: {
+		Template::<T>::remove_stale_commitments(Weight::MAX);
+	}
+	verify {
+		assert_eq!(Commitments::<T>::iter_prefix(0).count(), 0);
+	}
+
+	// The cost of `on_idle` removing the usage of `n` accounts that made feeless calls in a past
+	// era.
+	remove_stale_feeless_usage {
+		let n in 0 .. 1_000;
+		let past = T::BlockNumber::zero();
+		for i in 0..n {
+			FeelessUsage::<T>::insert(past, account::<T::AccountId>("feeless", i, 0), 1);
+		}
+		frame_system::Pallet::<T>::set_block_number(T::FeelessEraLength::get().max(One::one()));
+	}: {
+		Template::<T>::remove_stale_feeless_usage(Weight::MAX);
+	}
+	verify {
+		assert_eq!(FeelessUsage::<T>::iter_prefix(past).count(), 0);
+		assert_eq!(FeelessCleanupCursor::<T>::get(), One::one());
+	}
+
 	impl_benchmark_test_suite!(Template, crate::mock::new_test_ext(), crate::mock::Test);
 }
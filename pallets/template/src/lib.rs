@@ -5,19 +5,89 @@
 /// <https://docs.substrate.io/reference/frame-pallets/>
 pub use pallet::*;
 
-#[cfg(test)]
-mod mock;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod mock;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod proptests;
+
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+pub mod weights;
+pub use weights::WeightInfo;
+
+/// Authorizes feeless calls in the benchmarks, which cannot create signatures of a generic
+/// `Config::FeelessSignature` themselves.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AccountId, Signature> {
+	/// An account whose payloads [`Self::sign`] can sign, and that `Config::FeelessFilter`
+	/// accepts. Called in the setup of the benchmarks, so it may write the storage the filter
+	/// reads.
+	fn feeless_signer() -> AccountId;
+
+	/// Sign `payload` as `who`, an account returned by [`Self::feeless_signer`].
+	fn sign(who: &AccountId, payload: &[u8]) -> Signature;
+}
 
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::pallet_prelude::*;
+	use crate::WeightInfo;
+	use frame_support::{
+		pallet_prelude::*,
+		sp_std::vec::Vec,
+		traits::{Contains, Currency, Randomness, ReservableCurrency},
+	};
 	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{
+		Hash, IdentifyAccount, One, Saturating, TrailingZeroInput, UniqueSaturatedInto, Verify,
+		Zero,
+	};
+
+	/// Subject mixed into the randomness request made when drawing a winner.
+	const DRAW_SUBJECT: &[u8] = b"pallet-template/draw";
+
+	/// Context prefixed to the payload signed for a feeless call.
+	const FEELESS_CONTEXT: &[u8] = b"pallet-template/feeless";
+
+	/// `InvalidTransaction::Custom` code of feeless calls made by an account that used up its
+	/// feeless calls for the era.
+	pub const FEELESS_LIMIT_REACHED: u8 = 1;
+
+	/// `InvalidTransaction::Custom` code of feeless calls made by an account that
+	/// `Config::FeelessFilter` rejects. The same code as `pallet_allowlist::NOT_ALLOWLISTED`, so
+	/// that both ways of transacting report a rejected account alike.
+	pub const FEELESS_NOT_ALLOWED: u8 = 2;
+
+	/// Index of a commit-reveal round.
+	pub type RoundIndex = u32;
+
+	pub(crate) type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// A commitment together with the deposit reserved for storing it.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct Commitment<Hash, Balance> {
+		/// Hash of the committer and the secret.
+		pub hash: Hash,
+		/// Amount reserved from the committer, returned when the commitment is removed.
+		pub deposit: Balance,
+	}
+
+	/// Book-keeping for the round that is currently accepting commitments or reveals.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct RoundInfo<BlockNumber> {
+		/// The index of the round.
+		pub index: RoundIndex,
+		/// Last block (inclusive) in which commitments are accepted.
+		pub commit_end: BlockNumber,
+		/// Last block (inclusive) in which reveals are accepted.
+		pub reveal_end: BlockNumber,
+		/// Number of commitments made in this round.
+		pub commitments: u32,
+	}
 
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
@@ -28,6 +98,76 @@ pub mod pallet {
 	pub trait Config: frame_system::Config {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Source of on-chain randomness combined with the revealed secrets when drawing a winner.
+		///
+		/// In the node template runtime this is `pallet_randomness_collective_flip`, which is
+		/// derived from recent block hashes and can be influenced by block authors. It must not be
+		/// relied upon where real value is at stake.
+		type MyRandomness: Randomness<Self::Hash, Self::BlockNumber>;
+
+		/// Number of blocks, starting with the first commitment, during which commitments are
+		/// accepted.
+		#[pallet::constant]
+		type CommitPeriod: Get<Self::BlockNumber>;
+
+		/// Number of blocks after the commit period during which commitments can be revealed.
+		#[pallet::constant]
+		type RevealPeriod: Get<Self::BlockNumber>;
+
+		/// Maximum number of commitments accepted in a single round.
+		#[pallet::constant]
+		type MaxParticipants: Get<u32>;
+
+		/// The currency in which storage deposits are reserved.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Base deposit reserved for every commitment kept in storage.
+		#[pallet::constant]
+		type CommitmentDeposit: Get<BalanceOf<Self>>;
+
+		/// Additional deposit reserved per byte of storage a commitment occupies.
+		#[pallet::constant]
+		type ByteDeposit: Get<BalanceOf<Self>>;
+
+		/// Weight information for the calls and hooks of this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Signature by which an account authorizes a feeless call made on its behalf.
+		type FeelessSignature: Verify<Signer = Self::FeelessSigner> + Parameter;
+
+		/// Signer of a `FeelessSignature`, identifying the account.
+		type FeelessSigner: IdentifyAccount<AccountId = Self::AccountId>;
+
+		/// Accounts that may make feeless calls.
+		///
+		/// Feeless calls are unsigned transactions, so the signed extensions that restrict who may
+		/// transact, like an allowlist, do not apply to them. Point this at the same list.
+		type FeelessFilter: Contains<Self::AccountId>;
+
+		/// Number of feeless calls each account may make per feeless era.
+		#[pallet::constant]
+		type FeelessCallsPerEra: Get<u32>;
+
+		/// Length in blocks of the eras over which feeless calls are counted.
+		#[pallet::constant]
+		type FeelessEraLength: Get<Self::BlockNumber>;
+
+		/// Number of feeless calls accepted in a single block, whichever accounts make them.
+		///
+		/// Accounts cost nothing to create, so the per-account limit alone does not bound the
+		/// number of feeless calls. This limit does.
+		#[pallet::constant]
+		type MaxFeelessCallsPerBlock: Get<u32>;
+
+		/// Priority of feeless calls in the transaction pool. Set it below the priority of
+		/// fee-paying transactions so that feeless calls cannot crowd them out.
+		#[pallet::constant]
+		type FeelessPriority: Get<TransactionPriority>;
+
+		/// Signs feeless calls in the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: crate::BenchmarkHelper<Self::AccountId, Self::FeelessSignature>;
 	}
 
 	// The pallet's runtime storage items.
@@ -38,6 +178,69 @@ pub mod pallet {
 	// https://docs.substrate.io/main-docs/build/runtime-storage/#declaring-storage-items
 	pub type Something<T> = StorageValue<_, u32>;
 
+	/// The index that will be given to the next round.
+	#[pallet::storage]
+	pub type NextRoundIndex<T> = StorageValue<_, RoundIndex, ValueQuery>;
+
+	/// The round that is currently open, if any.
+	#[pallet::storage]
+	#[pallet::getter(fn current_round)]
+	pub type CurrentRound<T: Config> = StorageValue<_, RoundInfo<T::BlockNumber>>;
+
+	/// Commitments that have not been revealed yet, keyed by round and committer.
+	#[pallet::storage]
+	#[pallet::getter(fn commitment)]
+	pub type Commitments<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		RoundIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		Commitment<T::Hash, BalanceOf<T>>,
+	>;
+
+	/// Accounts that revealed their secret in the current round.
+	#[pallet::storage]
+	#[pallet::getter(fn revealed)]
+	pub type Revealed<T: Config> =
+		StorageValue<_, BoundedVec<T::AccountId, T::MaxParticipants>, ValueQuery>;
+
+	/// Accumulated hash of all secrets revealed in the current round.
+	#[pallet::storage]
+	pub type RevealedSeed<T: Config> = StorageValue<_, T::Hash, ValueQuery>;
+
+	/// The oldest closed round that may still hold unrevealed commitments. Advanced by `on_idle`.
+	#[pallet::storage]
+	pub type CleanupCursor<T> = StorageValue<_, RoundIndex, ValueQuery>;
+
+	/// Number of successful calls into this pallet in the current block. Reset in
+	/// `on_initialize`.
+	#[pallet::storage]
+	#[pallet::getter(fn block_activity)]
+	pub type BlockActivity<T> = StorageValue<_, u32, ValueQuery>;
+
+	/// Number of feeless calls made by each account, by feeless era, i.e. the block number
+	/// divided by `Config::FeelessEraLength`. Eras before the current one are removed in
+	/// `on_idle`.
+	#[pallet::storage]
+	pub type FeelessUsage<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		Blake2_128Concat,
+		T::AccountId,
+		u32,
+		ValueQuery,
+	>;
+
+	/// The oldest feeless era whose usage may not have been removed yet.
+	#[pallet::storage]
+	pub type FeelessCleanupCursor<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+
+	/// Number of feeless calls in the current block. Reset in `on_initialize`.
+	#[pallet::storage]
+	pub type FeelessCallsInBlock<T> = StorageValue<_, u32, ValueQuery>;
+
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/main-docs/build/events-errors/
 	#[pallet::event]
@@ -46,6 +249,18 @@ pub mod pallet {
 		/// Event documentation should end with an array that provides descriptive names for event
 		/// parameters. [something, who]
 		SomethingStored(u32, T::AccountId),
+		/// A new round was opened. [round, commit_end, reveal_end]
+		RoundStarted(RoundIndex, T::BlockNumber, T::BlockNumber),
+		/// A commitment was made. [round, who]
+		Committed(RoundIndex, T::AccountId),
+		/// A commitment was revealed. [round, who]
+		Revealed(RoundIndex, T::AccountId),
+		/// A winner was drawn from the accounts that revealed. The draw mixes the revealed
+		/// secrets with `Config::MyRandomness`; the last account to reveal can still bias the
+		/// outcome by withholding its reveal. [round, winner]
+		WinnerDrawn(RoundIndex, T::AccountId),
+		/// A round ended without any reveals, so no winner was drawn. [round]
+		RoundExpired(RoundIndex),
 	}
 
 	// Errors inform users that something went wrong.
@@ -55,6 +270,49 @@ pub mod pallet {
 		NoneValue,
 		/// Errors should have helpful documentation associated with them.
 		StorageOverflow,
+		/// There is no round currently open.
+		NoActiveRound,
+		/// The commit period of the current round has ended.
+		CommitPeriodOver,
+		/// The account already has a commitment in the current round.
+		AlreadyCommitted,
+		/// The current round has reached `Config::MaxParticipants` commitments.
+		TooManyParticipants,
+		/// Reveals are only accepted once the commit period has ended.
+		RevealPeriodNotStarted,
+		/// The reveal period of the current round has ended.
+		RevealPeriodOver,
+		/// The account has no unrevealed commitment in the current round.
+		NoCommitment,
+		/// The revealed secret does not hash to the commitment.
+		InvalidReveal,
+		/// A winner can only be drawn once the reveal period has ended. Drawing earlier would let
+		/// the caller pick a block whose randomness favours them.
+		RoundNotOver,
+		/// The feeless call is for another era or feeless call index, its signature is wrong, or
+		/// a limit on feeless calls was reached.
+		InvalidFeelessCall,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			<BlockActivity<T>>::kill();
+			<FeelessCallsInBlock<T>>::kill();
+			T::WeightInfo::on_initialize()
+		}
+
+		fn on_idle(_n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let used = Self::remove_stale_commitments(remaining_weight);
+			used.saturating_add(Self::remove_stale_feeless_usage(
+				remaining_weight.saturating_sub(used),
+			))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -64,7 +322,7 @@ pub mod pallet {
 	impl<T: Config> Pallet<T> {
 		/// An example dispatchable that takes a singles value as a parameter, writes the value to
 		/// storage and emits an event. This function must be dispatched by a signed extrinsic.
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		#[pallet::weight(T::WeightInfo::do_something(*something))]
 		pub fn do_something(origin: OriginFor<T>, something: u32) -> DispatchResult {
 			// Check that the extrinsic was signed and get the signer.
 			// This function will return an error if the extrinsic is not signed.
@@ -73,6 +331,7 @@ pub mod pallet {
 
 			// Update storage.
 			<Something<T>>::put(something);
+			Self::note_activity();
 
 			// Emit an event.
 			Self::deposit_event(Event::SomethingStored(something, who));
@@ -81,7 +340,7 @@ pub mod pallet {
 		}
 
 		/// An example dispatchable that may throw a custom error.
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1))]
+		#[pallet::weight(T::WeightInfo::cause_error())]
 		pub fn cause_error(origin: OriginFor<T>) -> DispatchResult {
 			let _who = ensure_signed(origin)?;
 
@@ -94,9 +353,423 @@ pub mod pallet {
 					let new = old.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
 					// Update the value in storage with the incremented result.
 					<Something<T>>::put(new);
+					Self::note_activity();
 					Ok(())
 				},
 			}
 		}
+
+		/// Like `do_something`, but dispatched by an unsigned extrinsic, so that accounts without
+		/// funds can use it. `who` authorizes the call by signing `Self::feeless_payload`, and
+		/// must be accepted by `Config::FeelessFilter`.
+		///
+		/// Each account may make `Config::FeelessCallsPerEra` feeless calls per era, numbered by
+		/// `index` from zero, and blocks take at most `Config::MaxFeelessCallsPerBlock` of them.
+		/// The checks are made when the transaction is validated, so calls over the limits never
+		/// make it into a block.
+		#[pallet::weight(T::WeightInfo::do_something_feeless())]
+		pub fn do_something_feeless(
+			origin: OriginFor<T>,
+			something: u32,
+			who: T::AccountId,
+			era: T::BlockNumber,
+			index: u32,
+			signature: T::FeelessSignature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::check_feeless_inclusion(something, &who, era, index, &signature)
+				.map_err(|_| Error::<T>::InvalidFeelessCall)?;
+
+			<FeelessUsage<T>>::insert(era, &who, index + 1);
+			<FeelessCallsInBlock<T>>::mutate(|n| *n = n.saturating_add(1));
+			<Something<T>>::put(something);
+			Self::note_activity();
+
+			Self::deposit_event(Event::SomethingStored(something, who));
+			Ok(())
+		}
+
+		/// Commit to a secret for the current round, opening a new round if none is active.
+		///
+		/// The commitment must be `T::Hashing::hash_of(&(who, secret))`. Binding the committer
+		/// into the hash stops others from replaying a commitment they have seen.
+		///
+		/// Reserves `Self::commitment_deposit()` from the caller until the commitment is removed,
+		/// either by revealing it or by the clean-up of closed rounds.
+		#[pallet::weight(T::WeightInfo::commit())]
+		pub fn commit(origin: OriginFor<T>, commitment: T::Hash) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+
+			let mut round = match <CurrentRound<T>>::get() {
+				Some(round) => {
+					ensure!(now <= round.commit_end, Error::<T>::CommitPeriodOver);
+					round
+				},
+				None => Self::next_round(now),
+			};
+			ensure!(
+				!<Commitments<T>>::contains_key(round.index, &who),
+				Error::<T>::AlreadyCommitted
+			);
+			ensure!(round.commitments < T::MaxParticipants::get(), Error::<T>::TooManyParticipants);
+
+			let deposit = Self::commitment_deposit();
+			T::Currency::reserve(&who, deposit)?;
+
+			if round.commitments == 0 {
+				<NextRoundIndex<T>>::put(round.index.wrapping_add(1));
+				Self::deposit_event(Event::RoundStarted(
+					round.index,
+					round.commit_end,
+					round.reveal_end,
+				));
+			}
+			round.commitments += 1;
+			<Commitments<T>>::insert(round.index, &who, Commitment { hash: commitment, deposit });
+			<CurrentRound<T>>::put(&round);
+			Self::note_activity();
+
+			Self::deposit_event(Event::Committed(round.index, who));
+			Ok(())
+		}
+
+		/// Reveal the secret behind an earlier commitment.
+		///
+		/// Only accepted after the commit period and up to the end of the reveal period.
+		#[pallet::weight(T::WeightInfo::reveal())]
+		pub fn reveal(origin: OriginFor<T>, secret: [u8; 32]) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+
+			let round = <CurrentRound<T>>::get().ok_or(Error::<T>::NoActiveRound)?;
+			ensure!(now > round.commit_end, Error::<T>::RevealPeriodNotStarted);
+			ensure!(now <= round.reveal_end, Error::<T>::RevealPeriodOver);
+
+			let commitment =
+				<Commitments<T>>::get(round.index, &who).ok_or(Error::<T>::NoCommitment)?;
+			ensure!(
+				T::Hashing::hash_of(&(&who, &secret)) == commitment.hash,
+				Error::<T>::InvalidReveal
+			);
+
+			<Revealed<T>>::try_append(&who).map_err(|_| Error::<T>::TooManyParticipants)?;
+			<RevealedSeed<T>>::mutate(|seed| *seed = T::Hashing::hash_of(&(*seed, secret)));
+			<Commitments<T>>::remove(round.index, &who);
+			T::Currency::unreserve(&who, commitment.deposit);
+			Self::note_activity();
+
+			Self::deposit_event(Event::Revealed(round.index, who));
+			Ok(())
+		}
+
+		/// Close the current round once its reveal period is over and draw a winner among the
+		/// accounts that revealed.
+		///
+		/// Anyone may call this. Commitments that were never revealed are left behind and removed
+		/// in `on_idle` once blocks have spare weight.
+		#[pallet::weight(T::WeightInfo::draw(T::MaxParticipants::get()))]
+		pub fn draw(origin: OriginFor<T>) -> DispatchResult {
+			let _who = ensure_signed(origin)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+
+			let round = <CurrentRound<T>>::get().ok_or(Error::<T>::NoActiveRound)?;
+			ensure!(now > round.reveal_end, Error::<T>::RoundNotOver);
+
+			<CurrentRound<T>>::kill();
+			let revealed = <Revealed<T>>::take();
+			let seed = <RevealedSeed<T>>::take();
+			Self::note_activity();
+
+			if revealed.is_empty() {
+				Self::deposit_event(Event::RoundExpired(round.index));
+				return Ok(())
+			}
+
+			let (random, _) = T::MyRandomness::random(&(DRAW_SUBJECT, seed).encode());
+			let pick = u32::decode(&mut TrailingZeroInput::new(random.as_ref()))
+				.expect("input is padded with zeroes; qed");
+			let winner = revealed[pick as usize % revealed.len()].clone();
+
+			Self::deposit_event(Event::WinnerDrawn(round.index, winner));
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		/// Accept feeless calls that are for the current era and signed by their account, and
+		/// that leave the account within its limit. Calls with a later `index` than the next one
+		/// of the account wait in the pool for the calls before them.
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let (something, who, era, index, signature) = match call {
+				Call::do_something_feeless { something, who, era, index, signature } =>
+					(something, who, era, index, signature),
+				_ => return InvalidTransaction::Call.into(),
+			};
+			let used = Self::check_feeless(*something, who, *era, *index, signature)?;
+
+			let era_end = era.saturating_add(One::one()).saturating_mul(Self::feeless_era_length());
+			let longevity = era_end.saturating_sub(<frame_system::Pallet<T>>::block_number());
+			let mut validity = ValidTransaction::with_tag_prefix("TemplateFeeless")
+				.priority(T::FeelessPriority::get())
+				.and_provides((who, era, index))
+				.longevity(longevity.unique_saturated_into())
+				.propagate(true);
+			if *index > used {
+				validity = validity.and_requires((who, era, index - 1));
+			}
+			validity.build()
+		}
+
+		fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+			match call {
+				Call::do_something_feeless { something, who, era, index, signature } =>
+					Self::check_feeless_inclusion(*something, who, *era, *index, signature),
+				_ => Err(InvalidTransaction::Call.into()),
+			}
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The payload `who` signs to authorize a feeless call of `do_something(something)`,
+		/// as its `index`th feeless call in `era`.
+		pub fn feeless_payload(
+			something: u32,
+			who: &T::AccountId,
+			era: T::BlockNumber,
+			index: u32,
+		) -> Vec<u8> {
+			let genesis = <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero());
+			(FEELESS_CONTEXT, genesis, something, who, era, index).encode()
+		}
+
+		/// The current feeless era, and the number of feeless calls `who` made in it.
+		pub fn feeless_calls(who: &T::AccountId) -> (T::BlockNumber, u32) {
+			let era = Self::feeless_era(<frame_system::Pallet<T>>::block_number());
+			(era, <FeelessUsage<T>>::get(era, who))
+		}
+
+		/// The feeless era that block `n` belongs to.
+		pub fn feeless_era(n: T::BlockNumber) -> T::BlockNumber {
+			n / Self::feeless_era_length()
+		}
+
+		/// `Config::FeelessEraLength`, treating a length of zero as one block.
+		fn feeless_era_length() -> T::BlockNumber {
+			T::FeelessEraLength::get().max(One::one())
+		}
+
+		/// Check that `who` may make feeless calls, and that the call is for the current era,
+		/// within the limit of `who` and not one it already made, and signed by `who`. Returns
+		/// the number of feeless calls `who` already made in the era.
+		fn check_feeless(
+			something: u32,
+			who: &T::AccountId,
+			era: T::BlockNumber,
+			index: u32,
+			signature: &T::FeelessSignature,
+		) -> Result<u32, TransactionValidityError> {
+			if !T::FeelessFilter::contains(who) {
+				return Err(InvalidTransaction::Custom(FEELESS_NOT_ALLOWED).into())
+			}
+			let (current_era, used) = Self::feeless_calls(who);
+			if era < current_era || (era == current_era && index < used) {
+				return Err(InvalidTransaction::Stale.into())
+			}
+			if era > current_era {
+				return Err(InvalidTransaction::Future.into())
+			}
+			if index >= T::FeelessCallsPerEra::get() {
+				return Err(InvalidTransaction::Custom(FEELESS_LIMIT_REACHED).into())
+			}
+			let payload = Self::feeless_payload(something, who, era, index);
+			if !signature.verify(&payload[..], who) {
+				return Err(InvalidTransaction::BadProof.into())
+			}
+			Ok(used)
+		}
+
+		/// [`Self::check_feeless`], and check that the call is the next one of `who` and that the
+		/// current block has room for it.
+		fn check_feeless_inclusion(
+			something: u32,
+			who: &T::AccountId,
+			era: T::BlockNumber,
+			index: u32,
+			signature: &T::FeelessSignature,
+		) -> Result<(), TransactionValidityError> {
+			let used = Self::check_feeless(something, who, era, index, signature)?;
+			if index > used {
+				return Err(InvalidTransaction::Future.into())
+			}
+			if <FeelessCallsInBlock<T>>::get() >= T::MaxFeelessCallsPerBlock::get() {
+				return Err(InvalidTransaction::ExhaustsResources.into())
+			}
+			Ok(())
+		}
+
+		/// The round that the first commitment made at `now` would open. Nothing is written until
+		/// that commitment succeeds.
+		fn next_round(now: T::BlockNumber) -> RoundInfo<T::BlockNumber> {
+			let commit_end = now + T::CommitPeriod::get();
+			let reveal_end = commit_end + T::RevealPeriod::get();
+			RoundInfo { index: <NextRoundIndex<T>>::get(), commit_end, reveal_end, commitments: 0 }
+		}
+
+		/// The deposit reserved for storing a single commitment.
+		pub fn commitment_deposit() -> BalanceOf<T> {
+			let bytes = Commitment::<T::Hash, BalanceOf<T>>::max_encoded_len() as u32;
+			T::CommitmentDeposit::get()
+				.saturating_add(T::ByteDeposit::get().saturating_mul(bytes.into()))
+		}
+
+		/// Count a successful call towards this block's activity.
+		fn note_activity() {
+			<BlockActivity<T>>::mutate(|n| *n = n.saturating_add(1));
+		}
+
+		/// Remove unrevealed commitments of closed rounds, oldest first, and return their deposits
+		/// without exceeding `remaining_weight`. Returns the weight consumed.
+		///
+		/// Every storage probe, whether it removes a commitment or finds a round exhausted, is
+		/// charged as one item of `WeightInfo::remove_stale_commitments`.
+		pub(crate) fn remove_stale_commitments(remaining_weight: Weight) -> Weight {
+			let base = T::WeightInfo::remove_stale_commitments(0);
+			let per_item = T::WeightInfo::remove_stale_commitments(1).saturating_sub(base).max(1);
+			if remaining_weight < base.saturating_add(per_item) {
+				return 0
+			}
+			let budget = (remaining_weight - base) / per_item;
+
+			// Rounds from the current (or next) one onwards are not closed yet.
+			let first_open =
+				<CurrentRound<T>>::get().map_or_else(<NextRoundIndex<T>>::get, |round| round.index);
+			let mut cursor = <CleanupCursor<T>>::get();
+			let mut used = 0;
+			'rounds: while cursor < first_open {
+				let mut stale = <Commitments<T>>::drain_prefix(cursor);
+				loop {
+					if used >= budget {
+						break 'rounds
+					}
+					used += 1;
+					match stale.next() {
+						Some((who, commitment)) => {
+							T::Currency::unreserve(&who, commitment.deposit);
+						},
+						None => break,
+					}
+				}
+				cursor.saturating_inc();
+			}
+			<CleanupCursor<T>>::put(cursor);
+
+			base.saturating_add(per_item.saturating_mul(used))
+		}
+
+		/// Remove the feeless usage of past eras, oldest first, without exceeding
+		/// `remaining_weight`. Returns the weight consumed.
+		///
+		/// As in [`Self::remove_stale_commitments`], every storage probe is charged as one item of
+		/// `WeightInfo::remove_stale_feeless_usage`. While no era is stale, only the cursor is
+		/// read.
+		pub(crate) fn remove_stale_feeless_usage(remaining_weight: Weight) -> Weight {
+			let check = T::DbWeight::get().reads(1);
+			if remaining_weight < check {
+				return 0
+			}
+			let current = Self::feeless_era(<frame_system::Pallet<T>>::block_number());
+			let mut cursor = <FeelessCleanupCursor<T>>::get();
+			if cursor >= current {
+				return check
+			}
+
+			let base = T::WeightInfo::remove_stale_feeless_usage(0);
+			let per_item = T::WeightInfo::remove_stale_feeless_usage(1).saturating_sub(base).max(1);
+			if remaining_weight < base.saturating_add(per_item) {
+				return check
+			}
+			let budget = (remaining_weight - base) / per_item;
+
+			let mut used = 0;
+			'eras: while cursor < current {
+				let mut stale = <FeelessUsage<T>>::drain_prefix(cursor);
+				loop {
+					if used >= budget {
+						break 'eras
+					}
+					used += 1;
+					if stale.next().is_none() {
+						break
+					}
+				}
+				cursor.saturating_inc();
+			}
+			<FeelessCleanupCursor<T>>::put(cursor);
+
+			base.saturating_add(per_item.saturating_mul(used))
+		}
+
+		/// Check the invariants that tie the rounds, commitments and deposits together.
+		#[cfg(any(feature = "try-runtime", test))]
+		pub fn do_try_state() -> Result<(), &'static str> {
+			use frame_support::sp_std::collections::btree_map::BTreeMap;
+
+			let next_index = <NextRoundIndex<T>>::get();
+			let revealed = <Revealed<T>>::get();
+			let first_open = match <CurrentRound<T>>::get() {
+				Some(round) => {
+					ensure!(
+						round.index.wrapping_add(1) == next_index,
+						"the current round is not the last one opened"
+					);
+					ensure!(round.commit_end <= round.reveal_end, "round periods are out of order");
+					ensure!(
+						round.commitments <= T::MaxParticipants::get(),
+						"round has more commitments than participants allowed"
+					);
+					let pending = <Commitments<T>>::iter_prefix(round.index).count();
+					ensure!(
+						pending + revealed.len() == round.commitments as usize,
+						"commitments of the current round are not accounted for"
+					);
+					ensure!(
+						revealed
+							.iter()
+							.all(|who| !<Commitments<T>>::contains_key(round.index, who)),
+						"revealed account still holds a commitment"
+					);
+					round.index
+				},
+				None => {
+					ensure!(revealed.is_empty(), "reveals recorded without an open round");
+					next_index
+				},
+			};
+
+			let cursor = <CleanupCursor<T>>::get();
+			ensure!(cursor <= first_open, "clean-up cursor is ahead of the closed rounds");
+
+			let mut deposits = BTreeMap::<T::AccountId, BalanceOf<T>>::new();
+			for (index, who, commitment) in <Commitments<T>>::iter() {
+				ensure!(
+					cursor <= index && index < next_index,
+					"commitment outside of the rounds awaiting reveal or clean-up"
+				);
+				let total = deposits.entry(who).or_default();
+				*total = total.saturating_add(commitment.deposit);
+			}
+			for (who, total) in deposits {
+				ensure!(
+					T::Currency::reserved_balance(&who) >= total,
+					"commitment deposits are not reserved"
+				);
+			}
+
+			Ok(())
+		}
 	}
 }
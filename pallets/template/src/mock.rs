@@ -1,9 +1,14 @@
 use crate as pallet_template;
-use frame_support::traits::{ConstU16, ConstU64};
+use frame_support::{
+	traits::{
+		ConstU16, ConstU32, ConstU64, Contains, GenesisBuild, OnFinalize, OnIdle, OnInitialize,
+	},
+	weights::Weight,
+};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
-	testing::Header,
+	testing::{Header, TestSignature, UintAuthorityId},
 	traits::{BlakeTwo256, IdentityLookup},
 };
 
@@ -18,6 +23,9 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system,
+		Timestamp: pallet_timestamp,
+		Balances: pallet_balances,
+		RandomnessCollectiveFlip: pallet_randomness_collective_flip,
 		TemplateModule: pallet_template,
 	}
 );
@@ -40,7 +48,7 @@ impl system::Config for Test {
 	type BlockHashCount = ConstU64<250>;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
@@ -49,11 +57,150 @@ impl system::Config for Test {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
+/// Milliseconds between two blocks of the mock chain.
+pub const BLOCK_TIME: u64 = 6_000;
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<{ BLOCK_TIME / 2 }>;
+	type WeightInfo = ();
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl pallet_randomness_collective_flip::Config for Test {}
+
 impl pallet_template::Config for Test {
 	type Event = Event;
+	type MyRandomness = RandomnessCollectiveFlip;
+	type CommitPeriod = ConstU64<5>;
+	type RevealPeriod = ConstU64<5>;
+	type MaxParticipants = ConstU32<3>;
+	type Currency = Balances;
+	type CommitmentDeposit = ConstU64<10>;
+	type ByteDeposit = ConstU64<1>;
+	type WeightInfo = ();
+	type FeelessSignature = TestSignature;
+	type FeelessSigner = UintAuthorityId;
+	type FeelessFilter = FeelessFilter;
+	type FeelessCallsPerEra = ConstU32<2>;
+	type FeelessEraLength = ConstU64<10>;
+	type MaxFeelessCallsPerBlock = ConstU32<3>;
+	type FeelessPriority = ConstU64<1>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+/// Account that may not make feeless calls.
+pub const FEELESS_DENIED: u64 = 66;
+
+/// Lets every account but [`FEELESS_DENIED`] make feeless calls.
+pub struct FeelessFilter;
+
+impl Contains<u64> for FeelessFilter {
+	fn contains(who: &u64) -> bool {
+		*who != FEELESS_DENIED
+	}
+}
+
+/// Signs feeless calls in the benchmarks with a [`TestSignature`].
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_template::BenchmarkHelper<u64, TestSignature> for MockBenchmarkHelper {
+	fn feeless_signer() -> u64 {
+		7
+	}
+
+	fn sign(who: &u64, payload: &[u8]) -> TestSignature {
+		TestSignature(*who, payload.to_vec())
+	}
 }
 
-// Build genesis storage according to the mock runtime.
+/// Initial free balance of the accounts `1..=4` endowed by default.
+pub const ENDOWMENT: u64 = 1_000;
+
+/// Builder for the externalities of the mock runtime.
+pub struct ExtBuilder {
+	balances: Vec<(u64, u64)>,
+}
+
+impl Default for ExtBuilder {
+	fn default() -> Self {
+		Self { balances: (1..=4).map(|who| (who, ENDOWMENT)).collect() }
+	}
+}
+
+impl ExtBuilder {
+	/// Replace the endowed accounts and their initial free balances.
+	pub fn balances(mut self, balances: Vec<(u64, u64)>) -> Self {
+		self.balances = balances;
+		self
+	}
+
+	// Build genesis storage according to the mock runtime.
+	pub fn build(self) -> sp_io::TestExternalities {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> { balances: self.balances }
+			.assimilate_storage(&mut t)
+			.unwrap();
+
+		let mut ext: sp_io::TestExternalities = t.into();
+		ext.execute_with(|| {
+			// Events are not deposited on the genesis block.
+			System::set_block_number(1);
+			Timestamp::set_timestamp(BLOCK_TIME);
+		});
+		ext
+	}
+}
+
+/// Externalities with the default endowments.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	ExtBuilder::default().build()
+}
+
+/// Advance the chain to block `n`, running the hooks of every pallet on the way and moving the
+/// timestamp by `BLOCK_TIME` per block. Blocks are treated as empty, so `on_idle` gets the whole
+/// block weight.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		TemplateModule::on_idle(System::block_number(), Weight::MAX);
+		RandomnessCollectiveFlip::on_finalize(System::block_number());
+		System::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		System::on_initialize(System::block_number());
+		Timestamp::set_timestamp(System::block_number() * BLOCK_TIME);
+		RandomnessCollectiveFlip::on_initialize(System::block_number());
+		TemplateModule::on_initialize(System::block_number());
+	}
+}
+
+/// The last event deposited in the current block.
+pub fn last_event() -> Event {
+	System::events().pop().expect("an event was deposited").event
+}
+
+/// Assert that the last events deposited in the current block are `expected`, in order.
+pub fn assert_events(expected: Vec<Event>) {
+	let events: Vec<Event> = System::events().into_iter().map(|r| r.event).collect();
+	assert!(
+		events.len() >= expected.len(),
+		"expected at least {} events, got {:?}",
+		expected.len(),
+		events
+	);
+	assert_eq!(&events[events.len() - expected.len()..], &expected[..]);
 }
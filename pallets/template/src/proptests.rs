@@ -0,0 +1,110 @@
+//! Property tests for `do_something` and `cause_error`, checked against a model of the stored
+//! value.
+
+use crate::{mock::*, Error};
+use frame_support::{assert_noop, assert_ok};
+use proptest::prelude::*;
+use sp_runtime::DispatchError;
+
+#[derive(Clone, Debug)]
+enum Caller {
+	Signed(u64),
+	Root,
+	None,
+}
+
+impl Caller {
+	fn origin(&self) -> Origin {
+		match self {
+			Caller::Signed(who) => Origin::signed(*who),
+			Caller::Root => Origin::root(),
+			Caller::None => Origin::none(),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+	DoSomething(Caller, u32),
+	CauseError(Caller),
+}
+
+fn caller() -> impl Strategy<Value = Caller> {
+	prop_oneof![
+		8 => (0u64..8).prop_map(Caller::Signed),
+		1 => Just(Caller::Root),
+		1 => Just(Caller::None),
+	]
+}
+
+/// Values are biased towards the top of the range so that `cause_error` regularly overflows.
+fn value() -> impl Strategy<Value = u32> {
+	prop_oneof![any::<u32>(), (u32::MAX - 3)..=u32::MAX, 0u32..4]
+}
+
+fn action() -> impl Strategy<Value = Action> {
+	prop_oneof![
+		(caller(), value()).prop_map(|(caller, value)| Action::DoSomething(caller, value)),
+		caller().prop_map(Action::CauseError),
+	]
+}
+
+proptest! {
+	#[test]
+	fn dispatchables_follow_the_model(actions in prop::collection::vec(action(), 1..64)) {
+		new_test_ext().execute_with(|| {
+			let mut expected: Option<u32> = None;
+
+			for action in actions {
+				match action {
+					Action::DoSomething(caller @ (Caller::Root | Caller::None), value) => {
+						assert_noop!(
+							TemplateModule::do_something(caller.origin(), value),
+							DispatchError::BadOrigin
+						);
+					},
+					Action::DoSomething(caller, value) => {
+						assert_ok!(TemplateModule::do_something(caller.origin(), value));
+						expected = Some(value);
+					},
+					Action::CauseError(caller @ (Caller::Root | Caller::None)) => {
+						assert_noop!(
+							TemplateModule::cause_error(caller.origin()),
+							DispatchError::BadOrigin
+						);
+					},
+					Action::CauseError(caller) => match expected {
+						None => assert_noop!(
+							TemplateModule::cause_error(caller.origin()),
+							Error::<Test>::NoneValue
+						),
+						Some(u32::MAX) => assert_noop!(
+							TemplateModule::cause_error(caller.origin()),
+							Error::<Test>::StorageOverflow
+						),
+						Some(value) => {
+							assert_ok!(TemplateModule::cause_error(caller.origin()));
+							expected = Some(value + 1);
+						},
+					},
+				}
+				assert_eq!(TemplateModule::something(), expected);
+			}
+		});
+	}
+
+	#[test]
+	fn cause_error_overflows_exactly_at_max(start in (u32::MAX - 16)..=u32::MAX) {
+		new_test_ext().execute_with(|| {
+			assert_ok!(TemplateModule::do_something(Origin::signed(1), start));
+			for value in start..u32::MAX {
+				assert_ok!(TemplateModule::cause_error(Origin::signed(1)));
+				assert_eq!(TemplateModule::something(), Some(value + 1));
+			}
+			assert_noop!(
+				TemplateModule::cause_error(Origin::signed(1)),
+				Error::<Test>::StorageOverflow
+			);
+		});
+	}
+}
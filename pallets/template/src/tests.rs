@@ -1,5 +1,22 @@
-use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok};
+use crate::{
+	mock::*, weights::WeightInfo, CleanupCursor, Commitments, Error, Event, FeelessCleanupCursor,
+	FeelessUsage, FEELESS_LIMIT_REACHED, FEELESS_NOT_ALLOWED,
+};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::DispatchResult,
+	traits::{OnIdle, OnInitialize},
+	unsigned::ValidateUnsigned,
+	weights::Weight,
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::TestSignature,
+	traits::{BlakeTwo256, Dispatchable, Hash},
+	transaction_validity::{
+		InvalidTransaction, TransactionSource, TransactionValidityError, ValidTransaction,
+	},
+};
 
 #[test]
 fn it_works_for_default_value() {
@@ -18,3 +35,517 @@ fn correct_error_for_none_value() {
 		assert_noop!(TemplateModule::cause_error(Origin::signed(1)), Error::<Test>::NoneValue);
 	});
 }
+
+fn commitment_of(who: u64, secret: [u8; 32]) -> H256 {
+	BlakeTwo256::hash_of(&(who, secret))
+}
+
+#[test]
+fn commit_opens_a_round() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])));
+
+		let round = TemplateModule::current_round().unwrap();
+		assert_eq!(
+			(round.index, round.commit_end, round.reveal_end, round.commitments),
+			(0, 6, 11, 1)
+		);
+		assert_eq!(
+			TemplateModule::commitment(0, 1).map(|c| c.hash),
+			Some(commitment_of(1, [1; 32]))
+		);
+		assert_events(vec![
+			pallet_balances::Event::Reserved { who: 1, amount: DEPOSIT }.into(),
+			Event::RoundStarted(0, 6, 11).into(),
+			Event::Committed(0, 1).into(),
+		]);
+	});
+}
+
+#[test]
+fn commit_rejects_duplicates_and_full_rounds() {
+	new_test_ext().execute_with(|| {
+		for who in 1..=3 {
+			assert_ok!(TemplateModule::commit(Origin::signed(who), commitment_of(who, [0; 32])));
+		}
+		assert_noop!(
+			TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])),
+			Error::<Test>::AlreadyCommitted
+		);
+		assert_noop!(
+			TemplateModule::commit(Origin::signed(4), commitment_of(4, [0; 32])),
+			Error::<Test>::TooManyParticipants
+		);
+	});
+}
+
+#[test]
+fn commit_times_out_after_commit_period() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])));
+		run_to_block(6);
+		assert_ok!(TemplateModule::commit(Origin::signed(2), commitment_of(2, [2; 32])));
+		run_to_block(7);
+		assert_noop!(
+			TemplateModule::commit(Origin::signed(3), commitment_of(3, [3; 32])),
+			Error::<Test>::CommitPeriodOver
+		);
+	});
+}
+
+#[test]
+fn reveal_only_accepted_during_reveal_period() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			TemplateModule::reveal(Origin::signed(1), [1; 32]),
+			Error::<Test>::NoActiveRound
+		);
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])));
+		assert_ok!(TemplateModule::commit(Origin::signed(2), commitment_of(2, [2; 32])));
+		assert_noop!(
+			TemplateModule::reveal(Origin::signed(1), [1; 32]),
+			Error::<Test>::RevealPeriodNotStarted
+		);
+
+		run_to_block(7);
+		assert_ok!(TemplateModule::reveal(Origin::signed(1), [1; 32]));
+		System::assert_last_event(Event::Revealed(0, 1).into());
+		assert_eq!(TemplateModule::commitment(0, 1), None);
+		assert_eq!(TemplateModule::revealed().into_inner(), vec![1]);
+
+		run_to_block(12);
+		assert_noop!(
+			TemplateModule::reveal(Origin::signed(2), [2; 32]),
+			Error::<Test>::RevealPeriodOver
+		);
+	});
+}
+
+#[test]
+fn reveal_must_match_commitment() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])));
+		// Replaying someone else's commitment does not help, the committer is part of the hash.
+		assert_ok!(TemplateModule::commit(Origin::signed(2), commitment_of(1, [1; 32])));
+		run_to_block(7);
+
+		assert_noop!(
+			TemplateModule::reveal(Origin::signed(1), [2; 32]),
+			Error::<Test>::InvalidReveal
+		);
+		assert_noop!(
+			TemplateModule::reveal(Origin::signed(2), [1; 32]),
+			Error::<Test>::InvalidReveal
+		);
+		assert_noop!(
+			TemplateModule::reveal(Origin::signed(3), [1; 32]),
+			Error::<Test>::NoCommitment
+		);
+		assert_ok!(TemplateModule::reveal(Origin::signed(1), [1; 32]));
+		assert_noop!(
+			TemplateModule::reveal(Origin::signed(1), [1; 32]),
+			Error::<Test>::NoCommitment
+		);
+	});
+}
+
+#[test]
+fn draw_picks_a_revealed_account() {
+	new_test_ext().execute_with(|| {
+		for who in 1..=3 {
+			assert_ok!(TemplateModule::commit(
+				Origin::signed(who),
+				commitment_of(who, [who as u8; 32])
+			));
+		}
+		run_to_block(7);
+		assert_ok!(TemplateModule::reveal(Origin::signed(1), [1; 32]));
+		assert_ok!(TemplateModule::reveal(Origin::signed(3), [3; 32]));
+
+		assert_noop!(TemplateModule::draw(Origin::signed(4)), Error::<Test>::RoundNotOver);
+		run_to_block(12);
+		assert_ok!(TemplateModule::draw(Origin::signed(4)));
+
+		let winner = match last_event() {
+			crate::mock::Event::TemplateModule(Event::WinnerDrawn(0, winner)) => winner,
+			other => panic!("unexpected event: {:?}", other),
+		};
+		assert!(winner == 1 || winner == 3);
+		assert_eq!(TemplateModule::current_round(), None);
+		assert!(TemplateModule::revealed().is_empty());
+		// The unrevealed commitment is left for `on_idle` to clean up.
+		assert!(TemplateModule::commitment(0, 2).is_some());
+		run_to_block(13);
+		assert_eq!(TemplateModule::commitment(0, 2), None);
+	});
+}
+
+#[test]
+fn round_without_reveals_expires() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(TemplateModule::draw(Origin::signed(1)), Error::<Test>::NoActiveRound);
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])));
+		run_to_block(12);
+
+		assert_ok!(TemplateModule::draw(Origin::signed(1)));
+		System::assert_last_event(Event::RoundExpired(0).into());
+		run_to_block(13);
+		assert_eq!(TemplateModule::commitment(0, 1), None);
+
+		// The next commitment opens a fresh round.
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])));
+		System::assert_has_event(Event::RoundStarted(1, 18, 23).into());
+	});
+}
+
+#[test]
+fn block_activity_is_reset_every_block() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
+		assert_ok!(TemplateModule::cause_error(Origin::signed(1)));
+		assert_eq!(TemplateModule::block_activity(), 2);
+
+		// Failed calls are not counted.
+		assert_noop!(
+			TemplateModule::reveal(Origin::signed(1), [0; 32]),
+			Error::<Test>::NoActiveRound
+		);
+		assert_eq!(TemplateModule::block_activity(), 2);
+
+		assert_eq!(TemplateModule::on_initialize(2), <() as WeightInfo>::on_initialize());
+		assert_eq!(TemplateModule::block_activity(), 0);
+	});
+}
+
+/// Close a round in which accounts `1..=3` committed and nobody revealed.
+fn close_unrevealed_round() {
+	for who in 1..=3 {
+		assert_ok!(TemplateModule::commit(Origin::signed(who), commitment_of(who, [0; 32])));
+	}
+	run_to_block(System::block_number() + 11);
+	assert_ok!(TemplateModule::draw(Origin::signed(1)));
+}
+
+#[test]
+fn on_idle_removes_stale_commitments_within_weight() {
+	new_test_ext().execute_with(|| {
+		close_unrevealed_round();
+		let block = System::block_number();
+		let weight_for = |n| <() as WeightInfo>::remove_stale_commitments(n);
+		let per_item = weight_for(1) - weight_for(0);
+
+		// Not even one item fits.
+		assert_eq!(TemplateModule::on_idle(block, weight_for(0)), 0);
+		assert_eq!(
+			TemplateModule::commitment(0, 1).map(|c| c.hash),
+			Some(commitment_of(1, [0; 32]))
+		);
+
+		// Room for two removals only.
+		assert_eq!(TemplateModule::on_idle(block, weight_for(2) + per_item / 2), weight_for(2));
+		assert_eq!(Commitments::<Test>::iter_prefix(0).count(), 1);
+		assert_eq!(CleanupCursor::<Test>::get(), 0);
+
+		// The last removal plus the probe that finds the round exhausted.
+		assert_eq!(TemplateModule::on_idle(block, Weight::MAX), weight_for(2));
+		assert_eq!(Commitments::<Test>::iter_prefix(0).count(), 0);
+		assert_eq!(CleanupCursor::<Test>::get(), 1);
+
+		// Nothing left to do.
+		assert_eq!(TemplateModule::on_idle(block, Weight::MAX), weight_for(0));
+	});
+}
+
+#[test]
+fn on_idle_leaves_open_rounds_alone() {
+	new_test_ext().execute_with(|| {
+		close_unrevealed_round();
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [0; 32])));
+
+		run_to_block(System::block_number() + 1);
+		assert_eq!(Commitments::<Test>::iter_prefix(0).count(), 0);
+		assert_eq!(CleanupCursor::<Test>::get(), 1);
+		assert_eq!(
+			TemplateModule::commitment(1, 1).map(|c| c.hash),
+			Some(commitment_of(1, [0; 32]))
+		);
+	});
+}
+
+/// Deposit for one commitment in the mock: `CommitmentDeposit` plus a byte deposit for a 32 byte
+/// hash and an 8 byte balance.
+const DEPOSIT: u64 = 10 + 40;
+
+#[test]
+fn commitment_deposit_matches_constants() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(TemplateModule::commitment_deposit(), DEPOSIT);
+	});
+}
+
+#[test]
+fn commit_reserves_deposit_and_reveal_refunds_it() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])));
+		assert_eq!(Balances::reserved_balance(1), DEPOSIT);
+		assert_eq!(Balances::free_balance(1), ENDOWMENT - DEPOSIT);
+		assert_eq!(TemplateModule::commitment(0, 1).map(|c| c.deposit), Some(DEPOSIT));
+
+		run_to_block(7);
+		assert_ok!(TemplateModule::reveal(Origin::signed(1), [1; 32]));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), ENDOWMENT);
+	});
+}
+
+#[test]
+fn stale_commitment_deposit_is_refunded_on_cleanup() {
+	new_test_ext().execute_with(|| {
+		close_unrevealed_round();
+		for who in 1..=3 {
+			assert_eq!(Balances::reserved_balance(who), DEPOSIT);
+		}
+
+		run_to_block(System::block_number() + 1);
+		for who in 1..=3 {
+			assert_eq!(Balances::reserved_balance(who), 0);
+			assert_eq!(Balances::free_balance(who), ENDOWMENT);
+		}
+	});
+}
+
+#[test]
+fn commit_requires_funds_for_deposit() {
+	ExtBuilder::default()
+		.balances(vec![(1, DEPOSIT - 1), (2, DEPOSIT)])
+		.build()
+		.execute_with(|| {
+			assert_noop!(
+				TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])),
+				pallet_balances::Error::<Test>::InsufficientBalance
+			);
+			assert_noop!(
+				TemplateModule::commit(Origin::signed(5), commitment_of(5, [5; 32])),
+				pallet_balances::Error::<Test>::InsufficientBalance
+			);
+			assert_eq!(TemplateModule::current_round(), None);
+
+			// The whole free balance can be reserved, the existential deposit only applies to the
+			// total balance.
+			assert_ok!(TemplateModule::commit(Origin::signed(2), commitment_of(2, [2; 32])));
+			assert_eq!(Balances::free_balance(2), 0);
+			assert_eq!(Balances::reserved_balance(2), DEPOSIT);
+		});
+}
+
+#[test]
+fn run_to_block_advances_the_timestamp() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Timestamp::get(), BLOCK_TIME);
+		run_to_block(4);
+		assert_eq!(Timestamp::get(), 4 * BLOCK_TIME);
+	});
+}
+
+#[test]
+fn try_state_holds_throughout_rounds() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::do_try_state());
+		for who in 1..=3 {
+			assert_ok!(TemplateModule::commit(
+				Origin::signed(who),
+				commitment_of(who, [who as u8; 32])
+			));
+		}
+		assert_ok!(TemplateModule::do_try_state());
+
+		run_to_block(7);
+		assert_ok!(TemplateModule::reveal(Origin::signed(2), [2; 32]));
+		assert_ok!(TemplateModule::do_try_state());
+
+		run_to_block(12);
+		assert_ok!(TemplateModule::draw(Origin::signed(4)));
+		assert_ok!(TemplateModule::do_try_state());
+
+		run_to_block(13);
+		assert_eq!(CleanupCursor::<Test>::get(), 1);
+		assert_ok!(TemplateModule::do_try_state());
+	});
+}
+
+#[test]
+fn try_state_detects_broken_invariants() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::commit(Origin::signed(1), commitment_of(1, [1; 32])));
+
+		Commitments::<Test>::remove(0, 1);
+		assert_eq!(
+			TemplateModule::do_try_state(),
+			Err("commitments of the current round are not accounted for")
+		);
+
+		Commitments::<Test>::insert(
+			0,
+			1,
+			crate::Commitment { hash: commitment_of(1, [1; 32]), deposit: DEPOSIT + 1 },
+		);
+		assert_eq!(TemplateModule::do_try_state(), Err("commitment deposits are not reserved"));
+	});
+}
+
+/// A feeless `do_something(something)` call, signed by `who` as its `index`th in the current era.
+fn feeless(who: u64, something: u32, index: u32) -> crate::Call<Test> {
+	let (era, _) = TemplateModule::feeless_calls(&who);
+	let payload = TemplateModule::feeless_payload(something, &who, era, index);
+	crate::Call::do_something_feeless {
+		something,
+		who,
+		era,
+		index,
+		signature: TestSignature(who, payload),
+	}
+}
+
+/// Apply `call` as an unsigned extrinsic, the way `Executive` does.
+fn apply_unsigned(call: crate::Call<Test>) -> Result<DispatchResult, TransactionValidityError> {
+	TemplateModule::pre_dispatch(&call)?;
+	Ok(Call::TemplateModule(call)
+		.dispatch(Origin::none())
+		.map(|_| ())
+		.map_err(|e| e.error))
+}
+
+fn validate(call: &crate::Call<Test>) -> Result<ValidTransaction, TransactionValidityError> {
+	TemplateModule::validate_unsigned(TransactionSource::External, call)
+}
+
+#[test]
+fn feeless_calls_need_no_funds() {
+	new_test_ext().execute_with(|| {
+		// Account 7 is not endowed.
+		let validity = validate(&feeless(7, 42, 0)).unwrap();
+		assert_eq!(validity.priority, 1);
+		assert!(validity.requires.is_empty());
+		assert_eq!(validity.longevity, 9);
+
+		assert_eq!(apply_unsigned(feeless(7, 42, 0)), Ok(Ok(())));
+		assert_eq!(TemplateModule::something(), Some(42));
+		assert_eq!(last_event(), Event::SomethingStored(42, 7).into());
+		assert_eq!(FeelessUsage::<Test>::get(0, 7), 1);
+		assert_eq!(Balances::free_balance(7), 0);
+	});
+}
+
+#[test]
+fn feeless_calls_are_limited_per_account_and_era() {
+	new_test_ext().execute_with(|| {
+		// Calls are made in order, and only once.
+		assert_eq!(apply_unsigned(feeless(7, 2, 1)), Err(InvalidTransaction::Future.into()));
+		assert_eq!(apply_unsigned(feeless(7, 1, 0)), Ok(Ok(())));
+		assert_eq!(apply_unsigned(feeless(7, 1, 0)), Err(InvalidTransaction::Stale.into()));
+		assert_eq!(apply_unsigned(feeless(7, 2, 1)), Ok(Ok(())));
+
+		let limit_reached: TransactionValidityError =
+			InvalidTransaction::Custom(FEELESS_LIMIT_REACHED).into();
+		assert_eq!(validate(&feeless(7, 3, 2)), Err(limit_reached));
+		// Other accounts have their own allowance.
+		assert_eq!(apply_unsigned(feeless(8, 3, 0)), Ok(Ok(())));
+
+		run_to_block(10);
+		assert_eq!(TemplateModule::feeless_calls(&7), (1, 0));
+		assert_eq!(apply_unsigned(feeless(7, 4, 0)), Ok(Ok(())));
+	});
+}
+
+#[test]
+fn on_idle_removes_feeless_usage_of_past_eras_within_weight() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(apply_unsigned(feeless(7, 1, 0)), Ok(Ok(())));
+		assert_eq!(apply_unsigned(feeless(8, 1, 0)), Ok(Ok(())));
+		let weight_for = |n| <() as WeightInfo>::remove_stale_feeless_usage(n);
+		let per_item = weight_for(1) - weight_for(0);
+
+		// The usage of the current era is kept.
+		run_to_block(10);
+		assert_eq!(FeelessUsage::<Test>::iter_prefix(0).count(), 2);
+
+		// Room for one removal only.
+		assert_eq!(
+			TemplateModule::remove_stale_feeless_usage(weight_for(1) + per_item / 2),
+			weight_for(1)
+		);
+		assert_eq!(FeelessUsage::<Test>::iter_prefix(0).count(), 1);
+		assert_eq!(FeelessCleanupCursor::<Test>::get(), 0);
+
+		// The last removal plus the probe that finds the era exhausted.
+		assert_eq!(TemplateModule::remove_stale_feeless_usage(Weight::MAX), weight_for(2));
+		assert_eq!(FeelessUsage::<Test>::iter_prefix(0).count(), 0);
+		assert_eq!(FeelessCleanupCursor::<Test>::get(), 1);
+
+		// Only the cursor is read until the next era, and the mock charges nothing for reads.
+		assert_eq!(TemplateModule::remove_stale_feeless_usage(Weight::MAX), 0);
+		assert_eq!(apply_unsigned(feeless(7, 2, 0)), Ok(Ok(())));
+		assert_eq!(FeelessUsage::<Test>::get(1, 7), 1);
+	});
+}
+
+#[test]
+fn feeless_calls_wait_for_earlier_ones_in_the_pool() {
+	new_test_ext().execute_with(|| {
+		let validity = validate(&feeless(7, 1, 1)).unwrap();
+		assert_eq!(validity.requires, validate(&feeless(7, 1, 0)).unwrap().provides);
+	});
+}
+
+#[test]
+fn feeless_calls_must_be_signed_by_their_account() {
+	new_test_ext().execute_with(|| {
+		let mut call = feeless(7, 42, 0);
+		if let crate::Call::do_something_feeless { signature, .. } = &mut call {
+			*signature = TestSignature(8, signature.1.clone());
+		}
+		assert_eq!(validate(&call), Err(InvalidTransaction::BadProof.into()));
+		// Dispatching directly still checks the signature.
+		assert_noop!(
+			Call::TemplateModule(call).dispatch(Origin::none()).map_err(|e| e.error),
+			Error::<Test>::InvalidFeelessCall
+		);
+		assert_noop!(
+			Call::TemplateModule(feeless(7, 42, 0))
+				.dispatch(Origin::signed(7))
+				.map_err(|e| e.error),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn feeless_calls_are_limited_to_accounts_of_the_filter() {
+	new_test_ext().execute_with(|| {
+		let call = feeless(FEELESS_DENIED, 42, 0);
+		let not_allowed: TransactionValidityError =
+			InvalidTransaction::Custom(FEELESS_NOT_ALLOWED).into();
+		assert_eq!(validate(&call), Err(not_allowed));
+		// Dispatching directly still checks the filter.
+		assert_noop!(
+			Call::TemplateModule(call).dispatch(Origin::none()).map_err(|e| e.error),
+			Error::<Test>::InvalidFeelessCall
+		);
+	});
+}
+
+#[test]
+fn feeless_calls_per_block_are_capped() {
+	new_test_ext().execute_with(|| {
+		for who in 10..13 {
+			assert_eq!(apply_unsigned(feeless(who, 1, 0)), Ok(Ok(())));
+		}
+		assert_eq!(
+			apply_unsigned(feeless(13, 1, 0)),
+			Err(InvalidTransaction::ExhaustsResources.into())
+		);
+
+		run_to_block(2);
+		assert_eq!(apply_unsigned(feeless(13, 1, 0)), Ok(Ok(())));
+	});
+}
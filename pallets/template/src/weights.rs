@@ -0,0 +1,218 @@
+//! Weights for pallet_template.
+//!
+//! PLACEHOLDERS, NOT BENCHMARK RESULTS. These numbers have never been measured: the storage
+//! accesses follow the code of each call and hook, but the execution times are round estimates
+//! picked by hand. The file only mimics the layout written by the `benchmark pallet` command so
+//! that running it replaces the file as a whole. Do that on reference hardware before relying on
+//! these weights, and again whenever a call, a hook or its benchmark changes:
+//!
+//! ```sh
+//! ./target/release/node-template benchmark pallet \
+//!     --chain dev \
+//!     --pallet pallet_template \
+//!     --extrinsic '*' \
+//!     --steps 50 \
+//!     --repeat 20 \
+//!     --output pallets/template/src/weights.rs
+//! ```
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_template.
+pub trait WeightInfo {
+	fn do_something(s: u32, ) -> Weight;
+	fn cause_error() -> Weight;
+	fn do_something_feeless() -> Weight;
+	fn commit() -> Weight;
+	fn reveal() -> Weight;
+	fn draw(n: u32, ) -> Weight;
+	fn on_initialize() -> Weight;
+	fn remove_stale_commitments(n: u32, ) -> Weight;
+	fn remove_stale_feeless_usage(n: u32, ) -> Weight;
+}
+
+/// Placeholder weights for pallet_template, pricing storage accesses with the runtime's `DbWeight`.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: TemplateModule Something (r:0 w:1)
+	fn do_something(_s: u32, ) -> Weight {
+		(16_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: TemplateModule Something (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	fn cause_error() -> Weight {
+		(17_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: Allowlist Allowlist (r:1 w:0)
+	// Storage: TemplateModule FeelessUsage (r:1 w:1)
+	// Storage: System BlockHash (r:1 w:0)
+	// Storage: TemplateModule FeelessCallsInBlock (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: TemplateModule Something (r:0 w:1)
+	fn do_something_feeless() -> Weight {
+		(61_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Storage: TemplateModule CurrentRound (r:1 w:1)
+	// Storage: TemplateModule Commitments (r:1 w:1)
+	// Storage: TemplateModule NextRoundIndex (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn commit() -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: TemplateModule CurrentRound (r:1 w:0)
+	// Storage: TemplateModule Commitments (r:1 w:1)
+	// Storage: TemplateModule Revealed (r:1 w:1)
+	// Storage: TemplateModule RevealedSeed (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn reveal() -> Weight {
+		(41_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: TemplateModule CurrentRound (r:1 w:1)
+	// Storage: TemplateModule Revealed (r:1 w:1)
+	// Storage: TemplateModule RevealedSeed (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: RandomnessCollectiveFlip RandomMaterial (r:1 w:0)
+	fn draw(n: u32, ) -> Weight {
+		(24_000_000 as Weight)
+			.saturating_add((45_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Storage: TemplateModule BlockActivity (r:0 w:1)
+	// Storage: TemplateModule FeelessCallsInBlock (r:0 w:1)
+	fn on_initialize() -> Weight {
+		(2_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: TemplateModule CurrentRound (r:1 w:0)
+	// Storage: TemplateModule NextRoundIndex (r:1 w:0)
+	// Storage: TemplateModule CleanupCursor (r:1 w:1)
+	// Storage: TemplateModule Commitments (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn remove_stale_commitments(n: u32, ) -> Weight {
+		(6_000_000 as Weight)
+			.saturating_add((9_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().reads((2 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: TemplateModule FeelessCleanupCursor (r:1 w:1)
+	// Storage: TemplateModule FeelessUsage (r:1 w:1)
+	fn remove_stale_feeless_usage(n: u32, ) -> Weight {
+		(3_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: TemplateModule Something (r:0 w:1)
+	fn do_something(_s: u32, ) -> Weight {
+		(16_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	// Storage: TemplateModule Something (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	fn cause_error() -> Weight {
+		(17_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	// Storage: Allowlist Allowlist (r:1 w:0)
+	// Storage: TemplateModule FeelessUsage (r:1 w:1)
+	// Storage: System BlockHash (r:1 w:0)
+	// Storage: TemplateModule FeelessCallsInBlock (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: TemplateModule Something (r:0 w:1)
+	fn do_something_feeless() -> Weight {
+		(61_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	// Storage: TemplateModule CurrentRound (r:1 w:1)
+	// Storage: TemplateModule Commitments (r:1 w:1)
+	// Storage: TemplateModule NextRoundIndex (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn commit() -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	// Storage: TemplateModule CurrentRound (r:1 w:0)
+	// Storage: TemplateModule Commitments (r:1 w:1)
+	// Storage: TemplateModule Revealed (r:1 w:1)
+	// Storage: TemplateModule RevealedSeed (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn reveal() -> Weight {
+		(41_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	// Storage: TemplateModule CurrentRound (r:1 w:1)
+	// Storage: TemplateModule Revealed (r:1 w:1)
+	// Storage: TemplateModule RevealedSeed (r:1 w:1)
+	// Storage: TemplateModule BlockActivity (r:1 w:1)
+	// Storage: RandomnessCollectiveFlip RandomMaterial (r:1 w:0)
+	fn draw(n: u32, ) -> Weight {
+		(24_000_000 as Weight)
+			.saturating_add((45_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	// Storage: TemplateModule BlockActivity (r:0 w:1)
+	// Storage: TemplateModule FeelessCallsInBlock (r:0 w:1)
+	fn on_initialize() -> Weight {
+		(2_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	// Storage: TemplateModule CurrentRound (r:1 w:0)
+	// Storage: TemplateModule NextRoundIndex (r:1 w:0)
+	// Storage: TemplateModule CleanupCursor (r:1 w:1)
+	// Storage: TemplateModule Commitments (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn remove_stale_commitments(n: u32, ) -> Weight {
+		(6_000_000 as Weight)
+			.saturating_add((9_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((2 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((2 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: TemplateModule FeelessCleanupCursor (r:1 w:1)
+	// Storage: TemplateModule FeelessUsage (r:1 w:1)
+	fn remove_stale_feeless_usage(n: u32, ) -> Weight {
+		(3_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+	}
+}
@@ -0,0 +1,49 @@
+//! Runtime APIs declared by this runtime, on top of the ones provided by Substrate.
+
+use crate::{AccountId, Balance, Event, Weight};
+use codec::{Decode, Encode};
+use pallet_nicknames::Nickname;
+use sp_runtime::{traits::Block as BlockT, ApplyExtrinsicResult, RuntimeDebug, RuntimeString};
+use sp_std::vec::Vec;
+
+/// What applying an extrinsic did, as reported by [`DryRunApi::dry_run`].
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct DryRunEffects {
+	/// Whether the extrinsic was valid and, if it was, whether its call succeeded.
+	pub result: ApplyExtrinsicResult,
+	/// The events deposited while applying the extrinsic.
+	pub events: Vec<Event>,
+	/// The weight the extrinsic used, after refunds. Zero if it was invalid.
+	pub actual_weight: Weight,
+	/// The fee the extrinsic paid, tip included. Zero if it was unsigned or invalid.
+	pub fee: Balance,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Checks of the invariants of the runtime's pallets.
+	///
+	/// Only implemented by runtimes built with the `try-runtime` feature.
+	pub trait TryStateApi {
+		/// Run the state checks of every pallet against the state of the block the call is made
+		/// at, returning the first violation found.
+		fn try_state() -> Result<(), RuntimeString>;
+	}
+
+	/// Simulation of extrinsics, without committing their effects.
+	pub trait DryRunApi {
+		/// Apply `extrinsic` to the block being built, as the block builder would, and report
+		/// its effects.
+		///
+		/// Callers first start a block on top of the one they simulate at with
+		/// `Core::initialize_block`, in the same changes, and drop the changes afterwards so that
+		/// they are never committed. Initializing the block is left to the caller so that it can
+		/// tell the writes of the extrinsic from those of the block initialization.
+		fn dry_run(extrinsic: <Block as BlockT>::Extrinsic) -> DryRunEffects;
+	}
+
+	/// Human-readable names of accounts.
+	pub trait IdentityApi {
+		/// The name of `account`, with its deposit and judgement, if it has one.
+		fn identity_of(account: AccountId) -> Option<Nickname<Vec<u8>, Balance>>;
+	}
+}
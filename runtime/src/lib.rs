@@ -6,6 +6,8 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+pub mod apis;
+
 use pallet_grandpa::{
 	fg_primitives, AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList,
 };
@@ -14,7 +16,10 @@ use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
+	traits::{
+		AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto, IdentifyAccount, NumberFor,
+		Verify,
+	},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
@@ -36,9 +41,14 @@ pub use frame_support::{
 	StorageValue,
 };
 pub use frame_system::Call as SystemCall;
+use frame_system::EnsureRoot;
+pub use pallet_allowlist::Call as AllowlistCall;
 pub use pallet_balances::Call as BalancesCall;
+pub use pallet_sudo::Call as SudoCall;
+pub use pallet_template::Call as TemplateCall;
 pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::CurrencyAdapter;
+pub use pallet_vesting::Call as VestingCall;
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
 pub use sp_runtime::{Perbill, Permill};
@@ -46,6 +56,15 @@ pub use sp_runtime::{Perbill, Permill};
 /// Import the template pallet.
 pub use pallet_template;
 
+/// Import the allowlist pallet.
+pub use pallet_allowlist;
+
+/// Import the nicknames pallet.
+pub use pallet_nicknames;
+
+/// Import the node authorization pallet, whose storage the node reads to pick its peers.
+pub use pallet_node_authorization;
+
 /// An index to a block.
 pub type BlockNumber = u32;
 
@@ -99,12 +118,15 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// The version of the runtime specification. A full node will not attempt to use its native
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
-	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
-	//   the compatible custom types.
-	spec_version: 100,
+	// This value started at 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
+	//   the compatible custom types. Bump it with every change to the runtime logic, calls,
+	//   storage or APIs, so that each change is an upgrade of its own.
+	spec_version: 109,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	// Bump with every change to the encoding of transactions: call indices, call arguments or
+	//   the signed extensions.
+	transaction_version: 3,
 	state_version: 1,
 };
 
@@ -260,6 +282,17 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ();
 }
 
+impl pallet_vesting::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BlockNumberToBalance = ConvertInto;
+	type MinVestedTransfer = ConstU128<{ 100 * EXISTENTIAL_DEPOSIT }>;
+	type WeightInfo = pallet_vesting::weights::SubstrateWeight<Runtime>;
+	// Each account holds a single vesting lock however many schedules it has, so this stays
+	// well within the `MaxLocks` of `pallet_balances`.
+	const MAX_VESTING_SCHEDULES: u32 = 28;
+}
+
 impl pallet_sudo::Config for Runtime {
 	type Event = Event;
 	type Call = Call;
@@ -268,6 +301,78 @@ impl pallet_sudo::Config for Runtime {
 /// Configure the pallet-template in pallets/template.
 impl pallet_template::Config for Runtime {
 	type Event = Event;
+	type MyRandomness = RandomnessCollectiveFlip;
+	type CommitPeriod = ConstU32<{ 10 * MINUTES }>;
+	type RevealPeriod = ConstU32<{ 10 * MINUTES }>;
+	type MaxParticipants = ConstU32<100>;
+	type Currency = Balances;
+	type CommitmentDeposit = ConstU128<{ 10 * EXISTENTIAL_DEPOSIT }>;
+	type ByteDeposit = ConstU128<{ EXISTENTIAL_DEPOSIT / 10 }>;
+	type WeightInfo = pallet_template::weights::SubstrateWeight<Runtime>;
+	type FeelessSignature = Signature;
+	type FeelessSigner = <Signature as Verify>::Signer;
+	// Feeless calls skip `CheckAllowlist` with the other signed extensions.
+	type FeelessFilter = Allowlist;
+	type FeelessCallsPerEra = ConstU32<5>;
+	type FeelessEraLength = ConstU32<DAYS>;
+	type MaxFeelessCallsPerBlock = ConstU32<50>;
+	// Fee-paying transactions always get a priority above zero, so feeless calls never displace
+	// them.
+	type FeelessPriority = ConstU64<0>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = TemplateBenchmarkHelper;
+}
+
+/// Signs feeless calls in the benchmarks of the template pallet with an sr25519 key generated
+/// in the keystore of the benchmark, whose account it adds to the allowlist.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct TemplateBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_template::BenchmarkHelper<AccountId, Signature> for TemplateBenchmarkHelper {
+	fn feeless_signer() -> AccountId {
+		let public =
+			frame_support::sp_io::crypto::sr25519_generate(sp_core::testing::SR25519, None);
+		let who: AccountId = sp_runtime::MultiSigner::from(public).into_account();
+		pallet_allowlist::Allowlist::<Runtime>::insert(&who, ());
+		who
+	}
+
+	fn sign(who: &AccountId, payload: &[u8]) -> Signature {
+		let public = sp_core::sr25519::Public::from_raw(who.clone().into());
+		frame_support::sp_io::crypto::sr25519_sign(sp_core::testing::SR25519, &public, payload)
+			.expect("the key was generated by `feeless_signer`; qed")
+			.into()
+	}
+}
+
+impl pallet_node_authorization::Config for Runtime {
+	type Event = Event;
+	type MaxWellKnownNodes = ConstU32<32>;
+	type MaxPeerIdLength = ConstU32<128>;
+	type AddOrigin = EnsureRoot<AccountId>;
+	type RemoveOrigin = EnsureRoot<AccountId>;
+	type SwapOrigin = EnsureRoot<AccountId>;
+	type ResetOrigin = EnsureRoot<AccountId>;
+	type WeightInfo = ();
+}
+
+/// Configure the pallet-allowlist in pallets/allowlist.
+impl pallet_allowlist::Config for Runtime {
+	type Event = Event;
+	type AdminOrigin = EnsureRoot<AccountId>;
+}
+
+/// Configure the pallet-nicknames in pallets/nicknames.
+impl pallet_nicknames::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type NameDeposit = ConstU128<{ 100 * EXISTENTIAL_DEPOSIT }>;
+	type MinLength = ConstU32<3>;
+	type MaxLength = ConstU32<32>;
+	type JudgeOrigin = EnsureRoot<AccountId>;
+	// Slashed deposits are burned.
+	type Slashed = ();
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -288,6 +393,10 @@ construct_runtime!(
 		Sudo: pallet_sudo,
 		// Include the custom logic from the pallet-template in the runtime.
 		TemplateModule: pallet_template,
+		Allowlist: pallet_allowlist,
+		NodeAuthorization: pallet_node_authorization,
+		Nicknames: pallet_nicknames,
+		Vesting: pallet_vesting,
 	}
 );
 
@@ -304,6 +413,7 @@ pub type SignedExtra = (
 	frame_system::CheckTxVersion<Runtime>,
 	frame_system::CheckGenesis<Runtime>,
 	frame_system::CheckEra<Runtime>,
+	pallet_allowlist::CheckAllowlist<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
@@ -312,13 +422,31 @@ pub type SignedExtra = (
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
+/// The pallets whose hooks `Executive` runs: all of them, in the order of `construct_runtime`,
+/// except `NodeAuthorization`. Its only hook is an offchain worker that restricts the peers of
+/// every node running offchain workers, as validators do by default. The node restricts its peers
+/// itself, and only when started with `--node-authorization`.
+pub type PalletsWithHooks = (
+	System,
+	RandomnessCollectiveFlip,
+	Timestamp,
+	Aura,
+	Grandpa,
+	Balances,
+	TransactionPayment,
+	Sudo,
+	TemplateModule,
+	Allowlist,
+	Nicknames,
+	Vesting,
+);
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
 	Block,
 	frame_system::ChainContext<Runtime>,
 	Runtime,
-	AllPalletsWithSystem,
+	PalletsWithHooks,
 >;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -333,9 +461,46 @@ mod benches {
 		[pallet_balances, Balances]
 		[pallet_timestamp, Timestamp]
 		[pallet_template, TemplateModule]
+		[pallet_vesting, Vesting]
 	);
 }
 
+/// Run the state checks of all pallets that define them.
+#[cfg(feature = "try-runtime")]
+pub fn try_state_all() -> Result<(), &'static str> {
+	TemplateModule::do_try_state()?;
+	Ok(())
+}
+
+/// Apply `extrinsic` to the block being built and report its effects, for
+/// [`apis::DryRunApi`].
+pub fn dry_run(extrinsic: <Block as BlockT>::Extrinsic) -> apis::DryRunEffects {
+	let first_event = System::event_count() as usize;
+	let result = Executive::apply_extrinsic(extrinsic);
+	let events: Vec<Event> = System::read_events_no_consensus()
+		.into_iter()
+		.skip(first_event)
+		.map(|record| record.event)
+		.collect();
+
+	let (mut actual_weight, mut fee) = (0, 0);
+	for event in &events {
+		match event {
+			Event::System(
+				frame_system::Event::ExtrinsicSuccess { dispatch_info } |
+				frame_system::Event::ExtrinsicFailed { dispatch_info, .. },
+			) => actual_weight = dispatch_info.weight,
+			Event::TransactionPayment(pallet_transaction_payment::Event::TransactionFeePaid {
+				actual_fee,
+				..
+			}) => fee = *actual_fee,
+			_ => {},
+		}
+	}
+
+	apis::DryRunEffects { result, events, actual_weight, fee }
+}
+
 impl_runtime_apis! {
 	impl sp_api::Core<Block> for Runtime {
 		fn version() -> RuntimeVersion {
@@ -484,6 +649,18 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl apis::DryRunApi<Block> for Runtime {
+		fn dry_run(extrinsic: <Block as BlockT>::Extrinsic) -> apis::DryRunEffects {
+			dry_run(extrinsic)
+		}
+	}
+
+	impl apis::IdentityApi<Block> for Runtime {
+		fn identity_of(account: AccountId) -> Option<pallet_nicknames::Nickname<Vec<u8>, Balance>> {
+			Nicknames::identity_of(&account)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (
@@ -549,4 +726,11 @@ impl_runtime_apis! {
 			Executive::execute_block_no_check(block)
 		}
 	}
+
+	#[cfg(feature = "try-runtime")]
+	impl apis::TryStateApi<Block> for Runtime {
+		fn try_state() -> Result<(), sp_runtime::RuntimeString> {
+			try_state_all().map_err(Into::into)
+		}
+	}
 }
@@ -0,0 +1,36 @@
+//! Integration tests of the `CheckAllowlist` signed extension.
+
+mod common;
+
+use common::*;
+use node_template_runtime::{AllowlistCall, Executive, SudoCall, SystemCall};
+use pallet_allowlist::NOT_ALLOWLISTED;
+use sp_keyring::Sr25519Keyring::{Alice, Bob, Charlie};
+use sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidityError};
+
+fn remark() -> SystemCall {
+	SystemCall::remark { remark: b"hello".to_vec() }
+}
+
+#[test]
+fn only_allowlisted_accounts_sign_transactions() {
+	new_test_ext().execute_with(|| {
+		let mut chain = Chain::new();
+		chain.initialize_block();
+		let not_allowlisted =
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(NOT_ALLOWLISTED)));
+
+		assert_eq!(Executive::apply_extrinsic(sign(Charlie, remark(), 0)), not_allowlisted);
+		assert_eq!(Executive::apply_extrinsic(sign(Bob, remark(), 0)), Ok(Ok(())));
+
+		let remove_bob = AllowlistCall::remove { who: Bob.to_account_id() };
+		let sudo = SudoCall::sudo { call: Box::new(remove_bob.into()) };
+		assert_eq!(Executive::apply_extrinsic(sign(Alice, sudo, 0)), Ok(Ok(())));
+		assert_eq!(Executive::apply_extrinsic(sign(Bob, remark(), 1)), not_allowlisted);
+
+		let add_bob = AllowlistCall::add { who: Bob.to_account_id() };
+		let sudo = SudoCall::sudo { call: Box::new(add_bob.into()) };
+		assert_eq!(Executive::apply_extrinsic(sign(Alice, sudo, 1)), Ok(Ok(())));
+		assert_eq!(Executive::apply_extrinsic(sign(Bob, remark(), 1)), Ok(Ok(())));
+	});
+}
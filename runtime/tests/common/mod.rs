@@ -0,0 +1,159 @@
+//! Shared helpers for the runtime integration tests.
+//!
+//! The tests drive the real `Runtime` through `Executive`, the same way the block builder of the
+//! node does, so signed extensions, fees and hooks all run as they would on chain.
+
+#![allow(dead_code)]
+
+use codec::Encode;
+use node_template_runtime::{
+	AccountId, AllowlistConfig, AuraConfig, Balance, BalancesConfig, BlockNumber, BuildStorage,
+	Call, Executive, GenesisConfig, GrandpaConfig, Header, Index, NicknamesConfig, Runtime,
+	SignedExtra, SignedPayload, SudoConfig, SystemConfig, TimestampCall, UncheckedExtrinsic,
+	VestingConfig, SLOT_DURATION, WASM_BINARY,
+};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_core::H256;
+use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
+use sp_runtime::{
+	generic::{DigestItem, Era},
+	traits::{Hash as _, Header as _},
+	ApplyExtrinsicResult, Digest,
+};
+
+/// Balance of every endowed account at genesis.
+pub const ENDOWMENT: Balance = 1 << 60;
+
+/// Genesis configuration equivalent to the `dev` chain spec of the node: Alice is the only
+/// authority and the sudo key, Alice, Bob and their stash accounts are endowed and
+/// allowlisted, Alice and Bob are named, and the endowment of Bob's stash vests.
+pub fn dev_genesis() -> GenesisConfig {
+	let endowed: Vec<AccountId> = vec![
+		Sr25519Keyring::Alice.to_account_id(),
+		Sr25519Keyring::Bob.to_account_id(),
+		Sr25519Keyring::AliceStash.to_account_id(),
+		Sr25519Keyring::BobStash.to_account_id(),
+	];
+
+	GenesisConfig {
+		system: SystemConfig { code: WASM_BINARY.map(|code| code.to_vec()).unwrap_or_default() },
+		balances: BalancesConfig {
+			balances: endowed.iter().cloned().map(|k| (k, ENDOWMENT)).collect(),
+		},
+		aura: AuraConfig { authorities: vec![Sr25519Keyring::Alice.public().into()] },
+		grandpa: GrandpaConfig { authorities: vec![(Ed25519Keyring::Alice.public().into(), 1)] },
+		sudo: SudoConfig { key: Some(Sr25519Keyring::Alice.to_account_id()) },
+		transaction_payment: Default::default(),
+		allowlist: AllowlistConfig { accounts: endowed },
+		node_authorization: Default::default(),
+		nicknames: NicknamesConfig {
+			names: vec![
+				(Sr25519Keyring::Alice.to_account_id(), b"Alice".to_vec()),
+				(Sr25519Keyring::Bob.to_account_id(), b"Bob".to_vec()),
+			],
+		},
+		// A tenth liquid, and the rest unlocking per block from block 10 on.
+		vesting: VestingConfig {
+			vesting: vec![(Sr25519Keyring::BobStash.to_account_id(), 10, 101, ENDOWMENT / 10)],
+		},
+	}
+}
+
+/// Externalities holding the `dev` genesis state.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	sp_io::TestExternalities::new(dev_genesis().build_storage().unwrap())
+}
+
+/// A chain of blocks built on top of the genesis state of the externalities it is used in.
+pub struct Chain {
+	parent_hash: H256,
+	number: BlockNumber,
+}
+
+impl Chain {
+	/// Start building on top of genesis. Must be called within the test externalities.
+	pub fn new() -> Self {
+		Self { parent_hash: frame_system::Pallet::<Runtime>::block_hash(0), number: 0 }
+	}
+
+	/// Number of the block currently being built, or of the last finalized block.
+	pub fn number(&self) -> BlockNumber {
+		self.number
+	}
+
+	/// Initialize the next block, authored in the slot with the same number, and apply the
+	/// timestamp inherent.
+	pub fn initialize_block(&mut self) {
+		self.number += 1;
+		let slot = Slot::from(self.number as u64);
+		let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+		let header = Header::new(
+			self.number,
+			Default::default(),
+			Default::default(),
+			self.parent_hash,
+			digest,
+		);
+		Executive::initialize_block(&header);
+
+		let timestamp = TimestampCall::set { now: self.number as u64 * SLOT_DURATION };
+		let inherent = UncheckedExtrinsic::new_unsigned(timestamp.into());
+		assert_eq!(Executive::apply_extrinsic(inherent), Ok(Ok(())));
+	}
+
+	/// Finalize the block currently being built.
+	pub fn finalize_block(&mut self) -> Header {
+		let header = Executive::finalize_block();
+		self.parent_hash = header.hash();
+		header
+	}
+
+	/// Build a whole block containing `extrinsics` and return their results.
+	pub fn build_block(
+		&mut self,
+		extrinsics: Vec<UncheckedExtrinsic>,
+	) -> Vec<ApplyExtrinsicResult> {
+		self.initialize_block();
+		let results = extrinsics.into_iter().map(Executive::apply_extrinsic).collect();
+		self.finalize_block();
+		results
+	}
+
+	/// Build empty blocks until block `number` is the one being built next.
+	pub fn advance_to(&mut self, number: BlockNumber) {
+		while self.number + 1 < number {
+			self.build_block(vec![]);
+		}
+	}
+}
+
+/// The signed extensions of an immortal transaction from an account with the given nonce.
+pub fn signed_extra(nonce: Index, tip: Balance) -> SignedExtra {
+	(
+		frame_system::CheckNonZeroSender::<Runtime>::new(),
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+		pallet_allowlist::CheckAllowlist::<Runtime>::new(),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+	)
+}
+
+/// Sign `call` as `signer`. Must be called within the test externalities, since the signed
+/// payload includes the genesis hash.
+pub fn sign(signer: Sr25519Keyring, call: impl Into<Call>, nonce: Index) -> UncheckedExtrinsic {
+	let call = call.into();
+	let extra = signed_extra(nonce, 0);
+	let payload = SignedPayload::new(call.clone(), extra.clone()).unwrap();
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+
+	UncheckedExtrinsic::new_signed(call, signer.to_account_id().into(), signature.into(), extra)
+}
+
+/// The commitment to `secret` expected from `who` by `TemplateModule::commit`.
+pub fn commitment_of(who: &AccountId, secret: [u8; 32]) -> H256 {
+	sp_runtime::traits::BlakeTwo256::hash_of(&(who, secret))
+}
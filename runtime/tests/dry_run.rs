@@ -0,0 +1,89 @@
+//! Integration tests of the effects reported by `DryRunApi`.
+
+mod common;
+
+use codec::Encode;
+use common::*;
+use frame_support::{storage::storage_prefix, StorageHasher, Twox64Concat};
+use node_template_runtime::{dry_run, Event, Runtime, TransactionPayment};
+use pallet_template::Call as TemplateCall;
+use sp_core::storage::well_known_keys;
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+use sp_runtime::{
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	DispatchError,
+};
+use std::collections::BTreeSet;
+
+#[test]
+fn dry_run_reports_result_events_weight_and_fee() {
+	new_test_ext().execute_with(|| {
+		let mut chain = Chain::new();
+		chain.initialize_block();
+
+		let effects = dry_run(sign(Bob, TemplateCall::cause_error {}, 0));
+		assert!(matches!(effects.result, Ok(Err(DispatchError::Module(_)))));
+		assert!(effects.fee > 0);
+		let failed = |event: &Event| {
+			matches!(event, Event::System(frame_system::Event::ExtrinsicFailed { .. }))
+		};
+		assert!(effects.events.iter().any(failed));
+
+		let xt = sign(Alice, TemplateCall::do_something { something: 42 }, 0);
+		let info = TransactionPayment::query_info(xt.clone(), xt.encoded_size() as u32);
+		let effects = dry_run(xt);
+		assert_eq!(effects.result, Ok(Ok(())));
+		assert_eq!(effects.fee, info.partial_fee);
+		assert!(effects.actual_weight > 0 && effects.actual_weight <= info.weight);
+		let stored = pallet_template::Event::SomethingStored(42, Alice.to_account_id()).into();
+		assert!(effects.events.contains(&stored));
+		// The events of earlier extrinsics in the block are not reported again.
+		assert!(!effects.events.iter().any(failed));
+
+		let effects = dry_run(sign(Alice, TemplateCall::do_something { something: 1 }, 5));
+		assert_eq!(
+			effects.result,
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Future))
+		);
+		assert!(effects.events.is_empty());
+		assert_eq!((effects.actual_weight, effects.fee), (0, 0));
+	});
+}
+
+#[test]
+fn dry_run_touches_only_the_keys_of_its_extrinsic() {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| Chain::new().initialize_block());
+	// Like `system_dryRunDecoded`, leave out what the block initialization wrote.
+	ext.commit_all().unwrap();
+
+	let xt = sign(Alice, TemplateCall::do_something { something: 42 }, 0);
+	assert_eq!(ext.execute_with(|| dry_run(xt)).result, Ok(Ok(())));
+
+	let touched: BTreeSet<Vec<u8>> =
+		ext.overlayed_changes().changes().map(|(key, _)| key.clone()).collect();
+	let value =
+		|pallet: &str, item: &str| storage_prefix(pallet.as_bytes(), item.as_bytes()).to_vec();
+	// The timestamp inherent is extrinsic 0.
+	let extrinsic_data =
+		[value("System", "ExtrinsicData"), 1u32.using_encoded(Twox64Concat::hash)].concat();
+	let expected: BTreeSet<Vec<u8>> = [
+		// Nonce and fee.
+		frame_system::Account::<Runtime>::hashed_key_for(Alice.to_account_id()),
+		// The burnt fee.
+		value("Balances", "TotalIssuance"),
+		value("TemplateModule", "Something"),
+		value("TemplateModule", "BlockActivity"),
+		// Bookkeeping of every applied extrinsic.
+		extrinsic_data,
+		value("System", "AllExtrinsicsLen"),
+		value("System", "BlockWeight"),
+		value("System", "Events"),
+		value("System", "EventCount"),
+		value("System", "ExecutionPhase"),
+		well_known_keys::EXTRINSIC_INDEX.to_vec(),
+	]
+	.into_iter()
+	.collect();
+	assert_eq!(touched, expected);
+}
@@ -0,0 +1,85 @@
+//! Integration tests of feeless `pallet_template` calls made by unfunded accounts on the
+//! allowlist.
+
+mod common;
+
+use common::*;
+use node_template_runtime::{
+	AccountId, AllowlistCall, Balances, Executive, SudoCall, System, TemplateModule,
+	UncheckedExtrinsic,
+};
+use pallet_allowlist::NOT_ALLOWLISTED;
+use pallet_template::Call as TemplateCall;
+use sp_keyring::Sr25519Keyring::{self, Alice, Bob, Charlie};
+use sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidityError};
+
+/// A feeless `do_something(something)` extrinsic for `who`, signed by `signer`.
+fn feeless(
+	signer: Sr25519Keyring,
+	who: AccountId,
+	something: u32,
+	index: u32,
+) -> UncheckedExtrinsic {
+	let (era, _) = TemplateModule::feeless_calls(&who);
+	let payload = TemplateModule::feeless_payload(something, &who, era, index);
+	let signature = signer.sign(&payload).into();
+	let call = TemplateCall::do_something_feeless { something, who, era, index, signature };
+	UncheckedExtrinsic::new_unsigned(call.into())
+}
+
+#[test]
+fn accounts_off_the_allowlist_cannot_make_feeless_calls() {
+	new_test_ext().execute_with(|| {
+		let mut chain = Chain::new();
+		chain.initialize_block();
+
+		assert_eq!(
+			Executive::apply_extrinsic(feeless(Charlie, Charlie.to_account_id(), 42, 0)),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(NOT_ALLOWLISTED)))
+		);
+		assert_eq!(TemplateModule::something(), None);
+	});
+}
+
+#[test]
+fn unfunded_accounts_make_feeless_calls_up_to_the_limit() {
+	new_test_ext().execute_with(|| {
+		let charlie = Charlie.to_account_id();
+		let mut chain = Chain::new();
+		chain.initialize_block();
+		let add_charlie = AllowlistCall::add { who: charlie.clone() };
+		let sudo = SudoCall::sudo { call: Box::new(add_charlie.into()) };
+		assert_eq!(Executive::apply_extrinsic(sign(Alice, sudo, 0)), Ok(Ok(())));
+
+		for index in 0..5 {
+			let result =
+				Executive::apply_extrinsic(feeless(Charlie, charlie.clone(), index, index));
+			assert_eq!(result, Ok(Ok(())));
+		}
+		assert_eq!(TemplateModule::something(), Some(4));
+		assert_eq!(Balances::free_balance(&charlie), 0);
+		assert_eq!(System::account_nonce(&charlie), 0);
+
+		assert_eq!(
+			Executive::apply_extrinsic(feeless(Charlie, charlie.clone(), 5, 5)),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(
+				pallet_template::FEELESS_LIMIT_REACHED
+			)))
+		);
+	});
+}
+
+#[test]
+fn feeless_calls_must_be_signed_by_their_account() {
+	new_test_ext().execute_with(|| {
+		let mut chain = Chain::new();
+		chain.initialize_block();
+
+		let forged = feeless(Charlie, Bob.to_account_id(), 42, 0);
+		assert_eq!(
+			Executive::apply_extrinsic(forged),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::BadProof))
+		);
+		assert_eq!(TemplateModule::something(), None);
+	});
+}
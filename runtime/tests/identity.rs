@@ -0,0 +1,41 @@
+//! Integration tests of account names, as reported by `IdentityApi`.
+
+mod common;
+
+use common::*;
+use node_template_runtime::{Balances, Executive, Nicknames, SudoCall, EXISTENTIAL_DEPOSIT};
+use pallet_nicknames::{Call as NicknamesCall, Judgement, Nickname};
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+
+#[test]
+fn names_are_set_and_judged() {
+	new_test_ext().execute_with(|| {
+		let mut chain = Chain::new();
+		chain.initialize_block();
+		let bob = Bob.to_account_id();
+		let deposit = 100 * EXISTENTIAL_DEPOSIT;
+
+		assert_eq!(
+			Nicknames::identity_of(&bob),
+			Some(Nickname { name: b"Bob".to_vec(), deposit: 0, judgement: Judgement::KnownGood })
+		);
+
+		let set_name = NicknamesCall::set_name { name: b"Bob the validator".to_vec() };
+		assert_eq!(Executive::apply_extrinsic(sign(Bob, set_name, 0)), Ok(Ok(())));
+		assert_eq!(Balances::reserved_balance(&bob), deposit);
+		assert_eq!(Nicknames::identity_of(&bob).unwrap().judgement, Judgement::Unknown);
+
+		let judge =
+			NicknamesCall::provide_judgement { who: bob.clone(), judgement: Judgement::KnownGood };
+		let sudo = SudoCall::sudo { call: Box::new(judge.into()) };
+		assert_eq!(Executive::apply_extrinsic(sign(Alice, sudo, 0)), Ok(Ok(())));
+		assert_eq!(
+			Nicknames::identity_of(&bob),
+			Some(Nickname {
+				name: b"Bob the validator".to_vec(),
+				deposit,
+				judgement: Judgement::KnownGood,
+			})
+		);
+	});
+}
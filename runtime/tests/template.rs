@@ -0,0 +1,160 @@
+//! Integration tests running `pallet_template` calls through the real runtime.
+
+mod common;
+
+use codec::Encode;
+use common::*;
+use node_template_runtime::{
+	AccountId, Balance, Balances, Event, Executive, System, TemplateModule, TransactionPayment,
+	MINUTES,
+};
+use pallet_template::Call as TemplateCall;
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+use sp_runtime::{
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	DispatchError,
+};
+
+/// The fee `who` paid for the transactions in the current block, as reported by the
+/// `TransactionFeePaid` events.
+fn fees_paid_by(who: &AccountId) -> Balance {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::TransactionPayment(pallet_transaction_payment::Event::TransactionFeePaid {
+				who: payer,
+				actual_fee,
+				..
+			}) if &payer == who => Some(actual_fee),
+			_ => None,
+		})
+		.sum()
+}
+
+fn has_event(event: impl Into<Event>) -> bool {
+	let event = event.into();
+	System::events().iter().any(|record| record.event == event)
+}
+
+#[test]
+fn signed_calls_pay_fees_and_bump_nonces_across_blocks() {
+	new_test_ext().execute_with(|| {
+		let alice = Alice.to_account_id();
+		let mut chain = Chain::new();
+
+		chain.initialize_block();
+		let xt = sign(Alice, TemplateCall::do_something { something: 42 }, 0);
+		let expected_fee =
+			TransactionPayment::query_info(xt.clone(), xt.encoded_size() as u32).partial_fee;
+		assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+
+		let fee = fees_paid_by(&alice);
+		assert!(fee > 0);
+		assert_eq!(fee, expected_fee);
+		assert_eq!(Balances::free_balance(&alice), ENDOWMENT - fee);
+		assert_eq!(System::account_nonce(&alice), 1);
+		assert_eq!(TemplateModule::something(), Some(42));
+		assert!(has_event(pallet_template::Event::SomethingStored(42, alice.clone())));
+		chain.finalize_block();
+
+		let results = chain.build_block(vec![sign(Alice, TemplateCall::cause_error {}, 1)]);
+		assert_eq!(results, vec![Ok(Ok(()))]);
+		assert_eq!(System::account_nonce(&alice), 2);
+		assert_eq!(TemplateModule::something(), Some(43));
+		assert_eq!(Balances::free_balance(&alice), ENDOWMENT - fee - fees_paid_by(&alice));
+	});
+}
+
+#[test]
+fn stale_and_future_nonces_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let mut chain = Chain::new();
+		chain.build_block(vec![sign(Alice, TemplateCall::do_something { something: 1 }, 0)]);
+
+		let results = chain.build_block(vec![
+			sign(Alice, TemplateCall::do_something { something: 2 }, 0),
+			sign(Alice, TemplateCall::do_something { something: 3 }, 5),
+		]);
+		assert_eq!(
+			results,
+			vec![
+				Err(TransactionValidityError::Invalid(InvalidTransaction::Stale)),
+				Err(TransactionValidityError::Invalid(InvalidTransaction::Future)),
+			]
+		);
+		assert_eq!(System::account_nonce(&Alice.to_account_id()), 1);
+		assert_eq!(TemplateModule::something(), Some(1));
+	});
+}
+
+#[test]
+fn failed_calls_still_pay_fees() {
+	new_test_ext().execute_with(|| {
+		let bob = Bob.to_account_id();
+		let mut chain = Chain::new();
+
+		chain.initialize_block();
+		let result = Executive::apply_extrinsic(sign(Bob, TemplateCall::cause_error {}, 0));
+		assert!(matches!(result, Ok(Err(DispatchError::Module(_)))));
+
+		let fee = fees_paid_by(&bob);
+		assert!(fee > 0);
+		assert_eq!(Balances::free_balance(&bob), ENDOWMENT - fee);
+		assert_eq!(System::account_nonce(&bob), 1);
+		assert_eq!(TemplateModule::something(), None);
+	});
+}
+
+#[test]
+fn commit_reveal_draw_round_through_the_runtime() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob) = (Alice.to_account_id(), Bob.to_account_id());
+		let deposit = TemplateModule::commitment_deposit();
+		let (alice_secret, bob_secret) = ([1u8; 32], [2u8; 32]);
+		let mut chain = Chain::new();
+
+		let results = chain.build_block(vec![
+			sign(
+				Alice,
+				TemplateCall::commit { commitment: commitment_of(&alice, alice_secret) },
+				0,
+			),
+			sign(Bob, TemplateCall::commit { commitment: commitment_of(&bob, bob_secret) }, 0),
+		]);
+		assert_eq!(results, vec![Ok(Ok(())), Ok(Ok(()))]);
+		assert_eq!(Balances::reserved_balance(&alice), deposit);
+		assert_eq!(Balances::reserved_balance(&bob), deposit);
+		let round = TemplateModule::current_round().unwrap();
+		assert_eq!(round.commit_end, 1 + 10 * MINUTES);
+		assert_eq!(round.commitments, 2);
+
+		// Revealing is only possible once the commit period is over.
+		let results =
+			chain.build_block(vec![sign(Alice, TemplateCall::reveal { secret: alice_secret }, 1)]);
+		assert!(matches!(results[..], [Ok(Err(DispatchError::Module(_)))]));
+
+		chain.advance_to(round.commit_end + 1);
+		let results = chain.build_block(vec![
+			sign(Alice, TemplateCall::reveal { secret: alice_secret }, 2),
+			sign(Bob, TemplateCall::reveal { secret: bob_secret }, 1),
+		]);
+		assert_eq!(results, vec![Ok(Ok(())), Ok(Ok(()))]);
+		assert_eq!(Balances::reserved_balance(&alice), 0);
+		assert_eq!(Balances::reserved_balance(&bob), 0);
+		assert_eq!(TemplateModule::revealed().len(), 2);
+
+		chain.advance_to(round.reveal_end + 1);
+		chain.initialize_block();
+		assert_eq!(Executive::apply_extrinsic(sign(Bob, TemplateCall::draw {}, 2)), Ok(Ok(())));
+		assert!(
+			has_event(pallet_template::Event::WinnerDrawn(round.index, alice.clone())) ||
+				has_event(pallet_template::Event::WinnerDrawn(round.index, bob.clone()))
+		);
+		chain.finalize_block();
+
+		assert!(TemplateModule::current_round().is_none());
+		assert!(TemplateModule::revealed().is_empty());
+		assert_eq!(System::account_nonce(&alice), 3);
+		assert_eq!(System::account_nonce(&bob), 3);
+	});
+}
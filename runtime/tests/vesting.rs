@@ -0,0 +1,43 @@
+//! Integration tests of the vesting schedules given to endowed accounts at genesis.
+
+mod common;
+
+use common::*;
+use node_template_runtime::{Balances, BalancesCall, BuildStorage, Runtime, Vesting, VestingCall};
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+
+/// Half of the endowment of Bob is locked, and unlocks over 100 blocks from block 10.
+fn vesting_ext() -> sp_io::TestExternalities {
+	let mut genesis = dev_genesis();
+	genesis.vesting.vesting = vec![(Bob.to_account_id(), 10, 100, ENDOWMENT / 2)];
+	sp_io::TestExternalities::new(genesis.build_storage().unwrap())
+}
+
+#[test]
+fn genesis_schedules_unlock_per_block() {
+	vesting_ext().execute_with(|| {
+		let mut chain = Chain::new();
+		let bob = Bob.to_account_id();
+		let locked = ENDOWMENT / 2;
+		let per_block = locked / 100;
+		assert_eq!(Vesting::vesting_balance(&bob), Some(locked));
+
+		let transfer = BalancesCall::transfer { dest: Alice.to_account_id().into(), value: locked };
+		assert_eq!(
+			chain.build_block(vec![sign(Bob, transfer, 0)]),
+			vec![Ok(Err(pallet_balances::Error::<Runtime>::LiquidityRestrictions.into()))]
+		);
+
+		// Half way through, `vest` releases what unlocked since block 10.
+		chain.advance_to(60);
+		assert_eq!(chain.build_block(vec![sign(Bob, VestingCall::vest {}, 1)]), vec![Ok(Ok(()))]);
+		assert_eq!(Balances::locks(&bob)[0].amount, locked - 50 * per_block);
+
+		// Once the schedule ended, anyone can remove the lock with `vest_other`.
+		chain.advance_to(111);
+		let vest_other = VestingCall::vest_other { target: bob.clone().into() };
+		assert_eq!(chain.build_block(vec![sign(Alice, vest_other, 0)]), vec![Ok(Ok(()))]);
+		assert!(Balances::locks(&bob).is_empty());
+		assert_eq!(Vesting::vesting(&bob), None);
+	});
+}